@@ -0,0 +1,137 @@
+use crate::components::note::Direction;
+use nalgebra::{Vector2, Vector3};
+
+// Metres between an end of an arc and its control point for a multiplier of 1
+pub const ARC_CONTROL_DISTANCE: f32 = 0.9;
+pub const RIBBON_WIDTH: f32 = 0.05;
+const RIBBON_SEGMENTS: usize = 32;
+
+/// Unit vector of a cut direction in grid space, +x is the right of the player and +y up.
+/// Dots have no direction and give a zero vector
+pub fn grid_direction(direction: Direction) -> Vector2<f32> {
+    let diagonal = std::f32::consts::FRAC_1_SQRT_2;
+    match direction {
+        Direction::Top => Vector2::new(0.0, 1.0),
+        Direction::Bottom => Vector2::new(0.0, -1.0),
+        Direction::Left => Vector2::new(-1.0, 0.0),
+        Direction::Right => Vector2::new(1.0, 0.0),
+        Direction::TopLeft => Vector2::new(-diagonal, diagonal),
+        Direction::TopRight => Vector2::new(diagonal, diagonal),
+        Direction::BottomLeft => Vector2::new(-diagonal, -diagonal),
+        Direction::BottomRight => Vector2::new(diagonal, -diagonal),
+        Direction::NoDirection => Vector2::zeros(),
+    }
+}
+
+/// A grid space vector across the playfield, grid cells aren't square
+pub fn playfield_vector(grid: Vector2<f32>) -> Vector2<f32> {
+    crate::songs::grid_position(grid.x, grid.y) - crate::songs::grid_position(0.0, 0.0)
+}
+
+/// Roll in degrees that turns the arrow of a note along a grid space vector
+pub fn roll_towards(grid: Vector2<f32>) -> f32 {
+    let vector = playfield_vector(grid);
+    // +x of the playfield is the left of the player, positive roll turns arrows to the right
+    (-vector.x).atan2(vector.y).to_degrees()
+}
+
+/// Links of a chain after its head, as grid positions and the grid direction of the chain
+/// at each link. `squish` below 1.0 moves the links towards the head
+pub fn chain_links(
+    head: Vector2<f32>,
+    direction: Direction,
+    tail: Vector2<f32>,
+    count: usize,
+    squish: f32,
+) -> Vec<(Vector2<f32>, Vector2<f32>)> {
+    // Quadratic curve that leaves the head in its cut direction
+    let control = head + grid_direction(direction) * (tail - head).norm() / 2.0;
+    (1..count)
+        .map(|i| {
+            let t = i as f32 / (count - 1) as f32 * squish;
+            let position =
+                head * (1.0 - t) * (1.0 - t) + control * 2.0 * t * (1.0 - t) + tail * t * t;
+            let tangent = (control - head) * 2.0 * (1.0 - t) + (tail - control) * 2.0 * t;
+            (position, tangent)
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RibbonVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub tex_coords: [f32; 2],
+}
+
+/// Cubic Bezier curve
+#[derive(Clone, Copy, Debug)]
+pub struct Bezier {
+    pub points: [Vector3<f32>; 4],
+}
+
+impl Bezier {
+    pub fn point(&self, t: f32) -> Vector3<f32> {
+        let [p0, p1, p2, p3] = self.points;
+        let u = 1.0 - t;
+        p0 * u * u * u + p1 * 3.0 * u * u * t + p2 * 3.0 * u * t * t + p3 * t * t * t
+    }
+
+    pub fn tangent(&self, t: f32) -> Vector3<f32> {
+        let [p0, p1, p2, p3] = self.points;
+        let u = 1.0 - t;
+        (p1 - p0) * 3.0 * u * u + (p2 - p1) * 6.0 * u * t + (p3 - p2) * 3.0 * t * t
+    }
+
+    /// Triangles of a flat ribbon along the curve. It lies as level as the curve allows so
+    /// the player sees it from above, both sides are drawn
+    pub fn ribbon(&self, width: f32) -> Vec<RibbonVertex> {
+        let edges: Vec<(Vector3<f32>, Vector3<f32>, Vector3<f32>)> = (0..=RIBBON_SEGMENTS)
+            .map(|i| {
+                let t = i as f32 / RIBBON_SEGMENTS as f32;
+                let point = self.point(t);
+                let tangent = self.tangent(t);
+                let side = Vector3::y().cross(&tangent);
+                let side = if side.norm() < 1.0e-4 {
+                    Vector3::x()
+                } else {
+                    side.normalize()
+                };
+                let normal = tangent
+                    .cross(&side)
+                    .try_normalize(1.0e-6)
+                    .unwrap_or(Vector3::y());
+                let half_width = side * width / 2.0;
+                (point - half_width, point + half_width, normal)
+            })
+            .collect();
+
+        let vertex = |position: Vector3<f32>, normal: Vector3<f32>, u: f32, v: f32| RibbonVertex {
+            position: position.into(),
+            normal: normal.into(),
+            tex_coords: [u, v],
+        };
+        let mut vertices = vec![];
+        for (i, pair) in edges.windows(2).enumerate() {
+            let (a, b, normal) = pair[0];
+            let (c, d, next_normal) = pair[1];
+            let v0 = i as f32 / RIBBON_SEGMENTS as f32;
+            let v1 = (i + 1) as f32 / RIBBON_SEGMENTS as f32;
+            let front = [
+                vertex(a, normal, 0.0, v0),
+                vertex(b, normal, 1.0, v0),
+                vertex(c, next_normal, 0.0, v1),
+                vertex(b, normal, 1.0, v0),
+                vertex(d, next_normal, 1.0, v1),
+                vertex(c, next_normal, 0.0, v1),
+            ];
+            vertices.extend_from_slice(&front);
+            // The back faces the other way with the opposite winding
+            vertices.extend(front.iter().rev().map(|vertex| RibbonVertex {
+                normal: [-vertex.normal[0], -vertex.normal[1], -vertex.normal[2]],
+                ..*vertex
+            }));
+        }
+        vertices
+    }
+}
@@ -0,0 +1,338 @@
+use crate::components::hit::note_hand;
+use crate::components::input::Hand;
+use crate::components::note::{Direction, Note, NoteType};
+use crate::components::obstacle::Obstacle;
+use crate::components::HIT_PLANE_Z;
+use crate::lanes::lane_rotation;
+use crate::timing::NoteJump;
+use nalgebra::{Isometry3, Translation3, UnitQuaternion, Vector2, Vector3};
+
+// Hands stay this far in front of the player, the blades turn around them
+const HAND_Z: f32 = 0.1;
+// Degrees the blade turns before and after a cut, a bit more than the full swing score needs
+const PRE_SWING_ANGLE: f32 = 105.0;
+const POST_SWING_ANGLE: f32 = 65.0;
+const PRE_SWING_MS: f32 = 250.0;
+const POST_SWING_MS: f32 = 200.0;
+// Swings take at most this share of the time to the next swing of the same hand
+const SWING_SHARE: f32 = 0.4;
+// Notes of one hand this close together are cut with one swing
+const CHAIN_MS: f32 = 40.0;
+// Hands go back to rest when there's time left between swings
+const REST_MS: f32 = 300.0;
+// Mines this close in time to a cut limit how far the blade turns towards them
+const MINE_WINDOW_MS: f32 = 80.0;
+// Distance from the swing plane that keeps a mine clear of the blade
+const MINE_CLEARANCE: f32 = 0.45;
+const MIN_SWING_ANGLE: f32 = 15.0;
+
+const HEAD_Z: f32 = 0.0;
+// Middle of the four lanes
+const GRID_CENTER_X: f32 = -0.05;
+// Places the head dodges to, tried in order: standing, crouching, leaning left and right
+const HEAD_POSITIONS: [(f32, f32); 6] = [
+    (GRID_CENTER_X, 1.7),
+    (GRID_CENTER_X, 0.9),
+    (0.65, 1.5),
+    (-0.75, 1.5),
+    (0.65, 0.9),
+    (-0.75, 0.9),
+];
+// Space kept between the head and walls
+const HEAD_MARGIN: f32 = 0.2;
+// Walls are dodged this long before they reach the head
+const DODGE_MS: f32 = 300.0;
+
+#[derive(Clone, Copy)]
+struct Keyframe {
+    time: f32,
+    grip: Isometry3<f32>,
+}
+
+struct Swing {
+    time: f32,
+    // Centre of the note on the hit plane
+    position: Vector3<f32>,
+    direction: Vector3<f32>,
+    // Lane rotation of the note in degrees, the rest of the swing is in the space of the lane
+    rotation: f32,
+    // Chain links are cut by moving the blade on from the previous swing
+    link: bool,
+}
+
+impl Swing {
+    /// Grip with the blade turned `angle` degrees from the note towards the cut direction
+    fn grip(&self, angle: f32) -> Isometry3<f32> {
+        let normal = Vector3::z().cross(&self.direction);
+        let angle = angle.to_radians();
+        let blade = Vector3::z() * angle.cos() + self.direction * angle.sin();
+        self.lane()
+            * Isometry3::from_parts(
+                Translation3::new(self.position.x, self.position.y, HAND_Z),
+                // The blade points along -z of the grip
+                UnitQuaternion::face_towards(&-blade, &normal),
+            )
+    }
+
+    fn lane(&self) -> Isometry3<f32> {
+        Isometry3::from_parts(Translation3::identity(), lane_rotation(self.rotation))
+    }
+
+    /// Pre and post swing angles that keep the blade away from mines in line with the note.
+    /// Mines that passed the hit plane are still in the way of the pre swing until they pass the hands
+    fn angles(&self, mines: &[&Note], pre_swing_ms: f32, mine_pass_ms: f32) -> (f32, f32) {
+        let reach = HIT_PLANE_Z - HAND_Z;
+        let across = Vector3::z().cross(&self.direction);
+        let mut angles = (PRE_SWING_ANGLE, POST_SWING_ANGLE);
+        for mine in mines.iter().filter(|mine| {
+            mine.rotation == self.rotation
+                && mine.time > self.time - pre_swing_ms - mine_pass_ms
+                && mine.time < self.time + MINE_WINDOW_MS
+        }) {
+            let position = crate::songs::note_position(mine);
+            let offset = Vector3::new(position.x, position.y, HIT_PLANE_Z) - self.position;
+            let along = offset.dot(&self.direction);
+            if offset.dot(&across).abs() > MINE_CLEARANCE || along.abs() < MINE_CLEARANCE {
+                continue;
+            }
+            let limit = ((along.abs() - MINE_CLEARANCE) / reach)
+                .atan()
+                .to_degrees()
+                .max(MIN_SWING_ANGLE);
+            if along > 0.0 {
+                if mine.time > self.time - MINE_WINDOW_MS {
+                    angles.1 = angles.1.min(limit);
+                }
+            } else {
+                angles.0 = angles.0.min(limit);
+            }
+        }
+        angles
+    }
+}
+
+struct Wall {
+    center: Vector2<f32>,
+    half_size: Vector2<f32>,
+    rotation: f32,
+    // Song time the head is inside the wall when it doesn't move
+    start: f32,
+    end: f32,
+}
+
+/// Saber and head movement that plays a map perfectly, planned when the song loads
+#[derive(Default)]
+pub struct AutoplayPlan {
+    hands: [Vec<Keyframe>; 2],
+    walls: Vec<Wall>,
+}
+
+impl AutoplayPlan {
+    pub fn new(notes: &[Note], obstacles: &[Obstacle], note_jump: &NoteJump) -> Self {
+        let mut notes: Vec<&Note> = notes.iter().collect();
+        notes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        let mines: Vec<&Note> = notes
+            .iter()
+            .cloned()
+            .filter(|note| matches!(note.note_type, NoteType::Mine))
+            .collect();
+        let hand_notes = |hand| -> Vec<&Note> {
+            notes
+                .iter()
+                .cloned()
+                .filter(|note| note_hand(&note.note_type) == Some(hand))
+                .collect()
+        };
+
+        // Walls pass the hit plane at their time and reach the head a bit later
+        let speed = note_jump.distance(1.0);
+        let head_delay = if speed > 0.0 {
+            (HIT_PLANE_Z - HEAD_Z) / speed
+        } else {
+            0.0
+        };
+        let walls = obstacles
+            .iter()
+            .map(|obstacle| {
+                let (center, half_size) = crate::songs::obstacle_footprint(obstacle);
+                Wall {
+                    center,
+                    half_size: Vector2::new(half_size.x.abs(), half_size.y.abs()),
+                    rotation: obstacle.rotation,
+                    start: obstacle.time + head_delay,
+                    end: obstacle.time + obstacle.duration + head_delay,
+                }
+            })
+            .collect();
+
+        let mine_pass_ms = MINE_WINDOW_MS + head_delay;
+        Self {
+            hands: [
+                plan_hand(Hand::Left, &hand_notes(Hand::Left), &mines, mine_pass_ms),
+                plan_hand(Hand::Right, &hand_notes(Hand::Right), &mines, mine_pass_ms),
+            ],
+            walls,
+        }
+    }
+
+    pub fn grip(&self, hand: Hand, time: f32) -> Isometry3<f32> {
+        let keyframes = &self.hands[hand as usize];
+        let next = match keyframes.binary_search_by(|keyframe| keyframe.time.total_cmp(&time)) {
+            Ok(i) => return keyframes[i].grip,
+            Err(i) => i,
+        };
+        match (
+            next.checked_sub(1).map(|i| keyframes[i]),
+            keyframes.get(next),
+        ) {
+            (Some(from), Some(to)) => interpolate(&from, to, time),
+            (Some(keyframe), None) | (None, Some(&keyframe)) => keyframe.grip,
+            (None, None) => rest_grip(hand),
+        }
+    }
+
+    /// Head position out of the walls around `time` when facing the lane turned `lane` degrees
+    pub fn head(&self, time: f32, lane: f32) -> Vector3<f32> {
+        HEAD_POSITIONS
+            .iter()
+            .map(|&(x, y)| lane_rotation(lane) * Vector3::new(x, y, HEAD_Z))
+            .find(|head| {
+                !self.walls.iter().any(|wall| {
+                    // The head in the space of the lane of the wall
+                    let head = lane_rotation(wall.rotation).inverse() * head;
+                    wall.start - DODGE_MS < time
+                        && time < wall.end
+                        && (head.x - wall.center.x).abs() < wall.half_size.x + HEAD_MARGIN
+                        && (head.y - wall.center.y).abs() < wall.half_size.y + HEAD_MARGIN
+                })
+            })
+            .unwrap_or_else(|| {
+                let (x, y) = HEAD_POSITIONS[0];
+                lane_rotation(lane) * Vector3::new(x, y, HEAD_Z)
+            })
+    }
+}
+
+fn plan_hand(hand: Hand, notes: &[&Note], mines: &[&Note], mine_pass_ms: f32) -> Vec<Keyframe> {
+    let mut swings: Vec<Swing> = vec![];
+    for note in notes {
+        if swings.last().map(|swing| note.time - swing.time < CHAIN_MS) == Some(true) {
+            continue;
+        }
+        let position = crate::songs::note_position(note);
+        let direction = match (&note.link, note.direction) {
+            // Dots are cut against the previous swing, starting with a down swing
+            (None, Direction::NoDirection) => -swings
+                .last()
+                .map(|swing| swing.direction)
+                .unwrap_or(Vector3::y()),
+            _ => crate::songs::note_rotation(note) * Vector3::y(),
+        };
+        swings.push(Swing {
+            time: note.time,
+            position: Vector3::new(position.x, position.y, HIT_PLANE_Z),
+            direction,
+            rotation: note.rotation,
+            link: note.link.is_some(),
+        });
+    }
+
+    let rest = rest_grip(hand);
+    let mut keyframes: Vec<Keyframe> = vec![];
+    for (i, swing) in swings.iter().enumerate() {
+        let before = match i {
+            0 => f32::INFINITY,
+            _ => swing.time - swings[i - 1].time,
+        };
+        let after = swings
+            .get(i + 1)
+            .map(|next| next.time - swing.time)
+            .unwrap_or(f32::INFINITY);
+        let pre_swing_ms = PRE_SWING_MS.min(before * SWING_SHARE);
+        let start = swing.time - pre_swing_ms;
+        let end = swing.time + POST_SWING_MS.min(after * SWING_SHARE);
+        let (pre_angle, post_angle) = swing.angles(mines, pre_swing_ms, mine_pass_ms);
+        // The blade stays straight from a chain head through its links
+        let next_link = swings.get(i + 1).map(|next| next.link) == Some(true);
+
+        if swing.link {
+            keyframes.push(Keyframe {
+                time: swing.time,
+                grip: swing.grip(0.0),
+            });
+            if !next_link {
+                keyframes.push(Keyframe {
+                    time: end,
+                    grip: swing.grip(post_angle),
+                });
+            }
+            continue;
+        }
+        let last_time = keyframes.last().map(|keyframe| keyframe.time);
+        if last_time.map(|time| start - time > REST_MS * 2.0) != Some(false) {
+            // Hands rest next to the lane of the swing before and after it
+            if let Some(time) = last_time {
+                keyframes.push(Keyframe {
+                    time: time + REST_MS,
+                    grip: swings[i - 1].lane() * rest,
+                });
+            }
+            keyframes.push(Keyframe {
+                time: start - REST_MS,
+                grip: swing.lane() * rest,
+            });
+        }
+        keyframes.push(Keyframe {
+            time: start,
+            grip: swing.grip(-pre_angle),
+        });
+        keyframes.push(Keyframe {
+            time: swing.time,
+            grip: swing.grip(0.0),
+        });
+        if !next_link {
+            keyframes.push(Keyframe {
+                time: end,
+                grip: swing.grip(post_angle),
+            });
+        }
+    }
+    if let (Some(time), Some(swing)) = (
+        keyframes.last().map(|keyframe| keyframe.time),
+        swings.last(),
+    ) {
+        keyframes.push(Keyframe {
+            time: time + REST_MS,
+            grip: swing.lane() * rest,
+        });
+    }
+    keyframes
+}
+
+/// Blade pointing at the floor next to the lanes, away from notes and mines
+fn rest_grip(hand: Hand) -> Isometry3<f32> {
+    let x = match hand {
+        Hand::Left => 1.6,
+        Hand::Right => -1.7,
+    };
+    Isometry3::from_parts(
+        Translation3::new(x, 1.0, HAND_Z),
+        UnitQuaternion::face_towards(&Vector3::y(), &Vector3::z()),
+    )
+}
+
+fn interpolate(from: &Keyframe, to: &Keyframe, time: f32) -> Isometry3<f32> {
+    let t = (time - from.time) / (to.time - from.time);
+    let translation = from.grip.translation.vector
+        + (to.grip.translation.vector - from.grip.translation.vector) * t;
+    let rotation = from
+        .grip
+        .rotation
+        .try_slerp(&to.grip.rotation, t, 1.0e-6)
+        .unwrap_or(if t < 0.5 {
+            from.grip.rotation
+        } else {
+            to.grip.rotation
+        });
+    Isometry3::from_parts(Translation3::from(translation), rotation)
+}
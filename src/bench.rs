@@ -0,0 +1,86 @@
+use crate::components::*;
+use crate::timing::NoteJump;
+use specs::Join;
+use std::time::{Duration, Instant};
+
+const FRAME_MS: f32 = 1000.0 / 90.0;
+
+fn dense_map(note_count: usize, notes_per_second: f32) -> spawn::SpawnQueue {
+    let notes = (0..note_count)
+        .map(|i| note::Note {
+            line_layer: (i / 4 % 3) as u8,
+            line_index: (i % 4) as u8,
+            note_type: if i % 2 == 0 {
+                note::NoteType::Red
+            } else {
+                note::NoteType::Blue
+            },
+            time: i as f32 * 1000.0 / notes_per_second,
+            direction: crate::parser::direction_from_raw((i % 9) as i64).unwrap(),
+            angle_offset: 0,
+            rotation: 0.0,
+            scoring: crate::scoring::ScoringType::Normal,
+            link: None,
+        })
+        .collect();
+    let obstacles = (0..note_count / 40)
+        .map(|i| obstacle::Obstacle {
+            line_index: (i % 2 * 3) as i32,
+            line_layer: 0,
+            width: 1,
+            height: 5,
+            time: (i * 40) as f32 * 1000.0 / notes_per_second,
+            duration: 500.0,
+            rotation: 0.0,
+        })
+        .collect();
+    spawn::SpawnQueue::new(notes, obstacles, vec![])
+}
+
+/// Plays a synthetic dense map without window and audio and prints how long gameplay frames take
+pub fn run_spawn_benchmark(note_count: usize, notes_per_second: f32) {
+    let mut world = specs::World::new();
+    register_default(&mut world);
+    world.add_resource(clock::SongClock::manual());
+    world.write_resource::<CurrentSongInfo>().note_jump = NoteJump::new(120.0, 18.0, 0.0);
+    world.add_resource(dense_map(note_count, notes_per_second));
+
+    let mut dispatcher = crate::game::gameplay_dispatcher();
+
+    let mut frame_times: Vec<Duration> = vec![];
+    let mut peak_objects = 0;
+    loop {
+        world.write_resource::<clock::SongClock>().advance(FRAME_MS);
+        let start = Instant::now();
+        dispatcher.dispatch(&world.res);
+        maintain(&mut world);
+        frame_times.push(start.elapsed());
+
+        // Nothing plays the slash sounds here
+        world.write_resource::<sound::SoundEvents>().queue.clear();
+        let objects = (&world.read_storage::<note::Note>()).join().count()
+            + (&world.read_storage::<obstacle::Obstacle>()).join().count();
+        peak_objects = peak_objects.max(objects);
+        if objects == 0 && world.read_resource::<spawn::SpawnQueue>().is_empty() {
+            break;
+        }
+    }
+
+    let total: Duration = frame_times.iter().sum();
+    frame_times.sort();
+    let percentile = |p: f32| frame_times[((frame_times.len() - 1) as f32 * p) as usize];
+    println!(
+        "{} notes at {} notes per second, {} frames",
+        note_count,
+        notes_per_second,
+        frame_times.len()
+    );
+    println!("Peak live objects: {}", peak_objects);
+    println!(
+        "Frame time: mean {:?}, p50 {:?}, p99 {:?}, max {:?}",
+        total / frame_times.len() as u32,
+        percentile(0.5),
+        percentile(0.99),
+        frame_times.last().unwrap()
+    );
+}
@@ -0,0 +1,231 @@
+use nalgebra::{Point3, UnitQuaternion, Vector3};
+
+// Largest angle between the swing and the arrow of a note that still counts as a good cut
+pub const CUT_ANGLE_TOLERANCE: f32 = 60.0;
+pub const STRICT_CUT_ANGLE_TOLERANCE: f32 = 45.0;
+
+/// Oriented bounding box
+#[derive(Clone, Copy, Debug)]
+pub struct Obb {
+    pub center: Point3<f32>,
+    pub rotation: UnitQuaternion<f32>,
+    pub half_extents: Vector3<f32>,
+}
+
+impl Obb {
+    pub fn local_point(&self, point: &Point3<f32>) -> Point3<f32> {
+        Point3::from(self.rotation.inverse() * (point - self.center))
+    }
+}
+
+/// Saber blade as a segment from the hilt to the tip
+#[derive(Clone, Copy, Debug)]
+pub struct Blade {
+    pub base: Point3<f32>,
+    pub tip: Point3<f32>,
+}
+
+impl Blade {
+    /// Point at `t` of the blade length, 0.0 is the base and 1.0 the tip
+    pub fn point(&self, t: f32) -> Point3<f32> {
+        self.base + (self.tip - self.base) * t
+    }
+    /// Position along the blade closest to `point`
+    pub fn closest_t(&self, point: &Point3<f32>) -> f32 {
+        let blade = self.tip - self.base;
+        let length_squared = blade.norm_squared();
+        if length_squared == 0.0 {
+            return 0.0;
+        }
+        ((point - self.base).dot(&blade) / length_squared).clamp(0.0, 1.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Cut {
+    // Movement of the blade at the point closest to the box centre, not normalized
+    pub direction: Vector3<f32>,
+    // Distance between the cut plane and the box centre
+    pub center_distance: f32,
+}
+
+/// Separating axis test of a triangle against a box
+pub fn triangle_intersects_obb(triangle: &[Point3<f32>; 3], obb: &Obb) -> bool {
+    let vertices = [
+        obb.local_point(&triangle[0]).coords,
+        obb.local_point(&triangle[1]).coords,
+        obb.local_point(&triangle[2]).coords,
+    ];
+    let edges = [
+        vertices[1] - vertices[0],
+        vertices[2] - vertices[1],
+        vertices[0] - vertices[2],
+    ];
+    let box_axes = [Vector3::x(), Vector3::y(), Vector3::z()];
+
+    let mut axes = Vec::with_capacity(13);
+    axes.extend_from_slice(&box_axes);
+    axes.push(edges[0].cross(&edges[1]));
+    for box_axis in &box_axes {
+        for edge in &edges {
+            axes.push(box_axis.cross(edge));
+        }
+    }
+
+    let half_extents = obb.half_extents;
+    !axes.iter().any(|axis| {
+        // Parallel edges give no axis
+        if axis.norm_squared() < 1e-12 {
+            return false;
+        }
+        let projections = [
+            vertices[0].dot(axis),
+            vertices[1].dot(axis),
+            vertices[2].dot(axis),
+        ];
+        let min = projections[0].min(projections[1]).min(projections[2]);
+        let max = projections[0].max(projections[1]).max(projections[2]);
+        let radius = half_extents.x * axis.x.abs()
+            + half_extents.y * axis.y.abs()
+            + half_extents.z * axis.z.abs();
+        min > radius || max < -radius
+    })
+}
+
+/// Tests the surface the blade swept between two frames against a box
+pub fn swept_blade_intersects_obb(from: &Blade, to: &Blade, obb: &Obb) -> bool {
+    triangle_intersects_obb(&[from.base, from.tip, to.tip], obb)
+        || triangle_intersects_obb(&[from.base, to.tip, to.base], obb)
+}
+
+pub fn cut(from: &Blade, to: &Blade, obb: &Obb) -> Option<Cut> {
+    if !swept_blade_intersects_obb(from, to, obb) {
+        return None;
+    }
+    let t = to.closest_t(&obb.center);
+    let direction = to.point(t) - from.point(t);
+    let normal = (to.tip - to.base).cross(&direction);
+    let center_distance = if normal.norm_squared() > 1e-12 {
+        (obb.center - to.base).dot(&normal.normalize()).abs()
+    } else {
+        (obb.center - to.point(t)).norm()
+    };
+    Some(Cut {
+        direction,
+        center_distance,
+    })
+}
+
+/// Checks a swing against the arrow of a note. Arrows point along +y of the note,
+/// `tolerance` is in degrees
+pub fn direction_matches(
+    cut_direction: &Vector3<f32>,
+    note_rotation: &UnitQuaternion<f32>,
+    tolerance: f32,
+) -> bool {
+    let local = note_rotation.inverse() * cut_direction;
+    let swing = nalgebra::Vector2::new(local.x, local.y);
+    if swing.norm_squared() < 1e-12 {
+        return false;
+    }
+    swing.normalize().y >= tolerance.to_radians().cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Note sized box one metre in front of the player at head height
+    fn note_box() -> Obb {
+        Obb {
+            center: Point3::new(0.0, 1.0, 1.0),
+            rotation: UnitQuaternion::identity(),
+            half_extents: Vector3::new(0.15, 0.15, 0.15),
+        }
+    }
+
+    // Blade held forward, at `x` across and `y` high
+    fn blade(x: f32, y: f32) -> Blade {
+        Blade {
+            base: Point3::new(x, y, 0.0),
+            tip: Point3::new(x, y, 1.5),
+        }
+    }
+
+    #[test]
+    fn downward_swing_through_the_box_cuts_it() {
+        let cut = cut(&blade(0.0, 1.4), &blade(0.0, 0.6), &note_box()).unwrap();
+        assert!(cut.direction.y < 0.0);
+        assert!(cut.center_distance < 1.0e-4);
+    }
+
+    #[test]
+    fn fast_swing_that_skips_over_the_box_between_frames_still_cuts_it() {
+        assert!(cut(&blade(0.05, 3.0), &blade(0.05, -1.0), &note_box()).is_some());
+    }
+
+    #[test]
+    fn off_centre_cut_measures_the_distance_to_the_centre() {
+        let cut = cut(&blade(0.1, 1.4), &blade(0.1, 0.6), &note_box()).unwrap();
+        assert!((cut.center_distance - 0.1).abs() < 1.0e-4);
+    }
+
+    #[test]
+    fn swing_just_outside_the_box_misses() {
+        assert!(cut(&blade(0.16, 1.4), &blade(0.16, 0.6), &note_box()).is_none());
+        assert!(cut(&blade(-0.16, 1.4), &blade(-0.16, 0.6), &note_box()).is_none());
+        // Stopping right above the box
+        assert!(cut(&blade(0.0, 1.6), &blade(0.0, 1.16), &note_box()).is_none());
+    }
+
+    #[test]
+    fn turned_box_is_tested_in_its_own_space() {
+        // Turned 45 degrees, its corner reaches further out than its side
+        let obb = Obb {
+            rotation: UnitQuaternion::from_euler_angles(0.0, 0.0, std::f32::consts::FRAC_PI_4),
+            ..note_box()
+        };
+        assert!(cut(&blade(0.2, 1.4), &blade(0.2, 0.6), &obb).is_some());
+        assert!(cut(&blade(0.2, 1.4), &blade(0.2, 0.6), &note_box()).is_none());
+    }
+
+    #[test]
+    fn swing_along_the_arrow_matches() {
+        // A down arrow is a note turned half a turn
+        let down = UnitQuaternion::from_euler_angles(0.0, 0.0, std::f32::consts::PI);
+        let tolerance = CUT_ANGLE_TOLERANCE;
+        assert!(direction_matches(&Vector3::new(0.0, -1.0, 0.0), &down, tolerance));
+        assert!(direction_matches(&Vector3::new(0.0, -0.3, 0.2), &down, tolerance));
+    }
+
+    #[test]
+    fn swing_against_the_arrow_doesnt_match() {
+        let down = UnitQuaternion::from_euler_angles(0.0, 0.0, std::f32::consts::PI);
+        let tolerance = CUT_ANGLE_TOLERANCE;
+        assert!(!direction_matches(&Vector3::new(0.0, 1.0, 0.0), &down, tolerance));
+        assert!(!direction_matches(&Vector3::new(1.0, 0.0, 0.0), &down, tolerance));
+        // Swinging straight at the note has no direction across it
+        assert!(!direction_matches(&Vector3::new(0.0, 0.0, 1.0), &down, tolerance));
+    }
+
+    #[test]
+    fn strict_angles_reject_wider_swings() {
+        let up = UnitQuaternion::identity();
+        let angle = 50.0f32.to_radians();
+        let swing = Vector3::new(angle.sin(), angle.cos(), 0.0);
+        assert!(direction_matches(&swing, &up, CUT_ANGLE_TOLERANCE));
+        assert!(!direction_matches(&swing, &up, STRICT_CUT_ANGLE_TOLERANCE));
+    }
+
+    #[test]
+    fn downward_cut_through_a_down_arrow_is_a_good_cut() {
+        let obb = Obb {
+            rotation: UnitQuaternion::from_euler_angles(0.0, 0.0, std::f32::consts::PI),
+            ..note_box()
+        };
+        let down = cut(&blade(0.0, 1.4), &blade(0.0, 0.6), &obb).unwrap();
+        assert!(direction_matches(&down.direction, &obb.rotation, CUT_ANGLE_TOLERANCE));
+        let up = cut(&blade(0.0, 0.6), &blade(0.0, 1.4), &obb).unwrap();
+        assert!(!direction_matches(&up.direction, &obb.rotation, CUT_ANGLE_TOLERANCE));
+    }
+}
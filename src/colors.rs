@@ -0,0 +1,120 @@
+use crate::components::{input::Hand, note::NoteType};
+use crate::lighting::{LIGHT_BLUE, LIGHT_RED};
+
+pub type Color = [f32; 3];
+
+const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+/// Colours of the notes, sabers, lights and walls, the active one is a resource
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorScheme {
+    // Left and right notes, also used for the sabers and arcs
+    pub notes: [Color; 2],
+    // Light events call these red and blue
+    pub lights: [Color; 2],
+    pub obstacles: Color,
+}
+
+impl Default for ColorScheme {
+    fn default() -> Self {
+        PRESETS[0].1
+    }
+}
+
+// The note shader replaces the red and blue of the textures, the default keeps them as drawn
+pub const PRESETS: [(&str, ColorScheme); 4] = [
+    (
+        "default",
+        ColorScheme {
+            notes: [[1.0, 0.0, 0.0], [0.0, 0.3, 1.0]],
+            lights: [LIGHT_RED, LIGHT_BLUE],
+            obstacles: [1.0, 0.2, 0.2],
+        },
+    ),
+    (
+        "sunset",
+        ColorScheme {
+            notes: [[1.0, 0.45, 0.0], [0.6, 0.1, 0.9]],
+            lights: [[0.95, 0.35, 0.1], [0.5, 0.15, 0.8]],
+            obstacles: [1.0, 0.5, 0.2],
+        },
+    ),
+    (
+        "forest",
+        ColorScheme {
+            notes: [[0.9, 0.75, 0.1], [0.1, 0.8, 0.3]],
+            lights: [[0.8, 0.6, 0.1], [0.1, 0.7, 0.35]],
+            obstacles: [0.6, 0.9, 0.4],
+        },
+    ),
+    (
+        "contrast",
+        ColorScheme {
+            notes: [[1.0, 0.0, 0.6], [0.0, 0.9, 1.0]],
+            lights: [[1.0, 0.1, 0.6], [0.1, 0.9, 1.0]],
+            obstacles: [1.0, 1.0, 1.0],
+        },
+    ),
+];
+
+pub fn preset(name: &str) -> Option<ColorScheme> {
+    PRESETS
+        .iter()
+        .find(|(preset, _)| preset.eq_ignore_ascii_case(name))
+        .map(|(_, colors)| *colors)
+}
+
+impl ColorScheme {
+    pub fn with_overrides(mut self, overrides: &ColorOverrides) -> Self {
+        for (color, replacement) in self.notes.iter_mut().zip(overrides.notes.iter()) {
+            *color = replacement.unwrap_or(*color);
+        }
+        for (color, replacement) in self.lights.iter_mut().zip(overrides.lights.iter()) {
+            *color = replacement.unwrap_or(*color);
+        }
+        self.obstacles = overrides.obstacles.unwrap_or(self.obstacles);
+        self
+    }
+
+    /// Tint of a note, mines keep their texture
+    pub fn note_tint(&self, note_type: NoteType) -> [f32; 4] {
+        match note_type {
+            NoteType::Red => self.hand_tint(Hand::Left),
+            NoteType::Blue => self.hand_tint(Hand::Right),
+            NoteType::Mine => WHITE,
+        }
+    }
+
+    pub fn hand_tint(&self, hand: Hand) -> [f32; 4] {
+        let [r, g, b] = match hand {
+            Hand::Left => self.notes[0],
+            Hand::Right => self.notes[1],
+        };
+        [r, g, b, 1.0]
+    }
+}
+
+/// Colours a map replaces, the others come from the preset of the player
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ColorOverrides {
+    pub notes: [Option<Color>; 2],
+    pub lights: [Option<Color>; 2],
+    pub obstacles: Option<Color>,
+}
+
+impl ColorOverrides {
+    /// Takes the colours `self` leaves alone from `other`
+    pub fn or(self, other: &ColorOverrides) -> Self {
+        Self {
+            notes: [
+                self.notes[0].or(other.notes[0]),
+                self.notes[1].or(other.notes[1]),
+            ],
+            lights: [
+                self.lights[0].or(other.lights[0]),
+                self.lights[1].or(other.lights[1]),
+            ],
+            obstacles: self.obstacles.or(other.obstacles),
+        }
+    }
+}
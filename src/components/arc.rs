@@ -0,0 +1,103 @@
+use crate::arcs::Bezier;
+use crate::components::*;
+use nalgebra::Point3;
+use note::{Direction, NoteType};
+use specs::{Component, Join, VecStorage};
+
+// Blades this close to the arc where it crosses the hit plane are holding it
+const HOLD_DISTANCE: f32 = 0.15;
+
+/// Ribbon from a head to a tail note of the same colour. Like in Beat Saber its ends score
+/// their swing in full whether or not the saber followed it, see `scoring::ScoringType`
+#[derive(Component)]
+#[storage(VecStorage)]
+pub struct Arc {
+    pub note_type: NoteType,
+    // Song time of the head and the tail in ms
+    pub time: f32,
+    pub tail_time: f32,
+    // Grid positions and cut directions of both ends
+    pub line_index: u8,
+    pub line_layer: u8,
+    pub direction: Direction,
+    pub tail_line_index: u8,
+    pub tail_line_layer: u8,
+    pub tail_direction: Direction,
+    // How far the arc bulges out of each end, 1.0 is `arcs::ARC_CONTROL_DISTANCE`
+    pub multiplier: f32,
+    pub tail_multiplier: f32,
+    // Degrees the lane of the arc is turned around the player, see `lanes`
+    pub rotation: f32,
+}
+
+/// Shape of a spawned arc in the space of its lane, the head is at z = 0.
+/// The renderer makes the ribbon mesh from it
+#[derive(Component)]
+#[storage(VecStorage)]
+pub struct Curve {
+    pub bezier: Bezier,
+}
+
+/// Moves arcs towards the player and rumbles the saber that holds one, holding doesn't score
+pub struct ArcSystem;
+
+impl<'a> specs::System<'a> for ArcSystem {
+    type SystemData = (
+        specs::Entities<'a>,
+        specs::Read<'a, CurrentSongInfo>,
+        specs::Read<'a, clock::SongClock>,
+        specs::Write<'a, RemoveEntities>,
+        specs::Write<'a, input::HapticEvents>,
+        specs::ReadStorage<'a, saber::Saber>,
+        specs::ReadStorage<'a, Arc>,
+        specs::ReadStorage<'a, Curve>,
+        specs::WriteStorage<'a, transform::Transform>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            ents,
+            song_info,
+            clock,
+            mut ents_to_remove,
+            mut haptics,
+            sabers,
+            arcs,
+            curves,
+            mut transforms,
+        ): Self::SystemData,
+    ) {
+        let note_jump = &song_info.note_jump;
+        let time = clock.time();
+        for (ent, arc, curve, transform) in (&ents, &arcs, &curves, &mut transforms).join() {
+            let distance = note_jump.distance(arc.time - time) + HIT_PLANE_Z;
+            transform.position = crate::songs::lane_position(arc.rotation, 0.0, 0.0, distance);
+            if note_jump.distance(arc.tail_time - time) < -hit::MISS_DISTANCE {
+                ents_to_remove.0.push(ent);
+                continue;
+            }
+            if time < arc.time || time > arc.tail_time {
+                continue;
+            }
+
+            // The curve moves along z as fast as time passes, so this is where it crosses the hit plane
+            let t = (time - arc.time) / (arc.tail_time - arc.time).max(1.0);
+            let point =
+                transform.position * (transform.rotation * Point3::from(curve.bezier.point(t)));
+            let holding = (&sabers).join().find(|saber| {
+                hit::note_hand(&arc.note_type) == Some(saber.hand)
+                    && saber.blade.map(|blade| {
+                        (blade.point(blade.closest_t(&point)) - point).norm() < HOLD_DISTANCE
+                    }) == Some(true)
+            });
+            if let Some(saber) = holding {
+                haptics.queue.push(input::HapticEvent {
+                    hand: saber.hand,
+                    duration_ms: 20.0,
+                    amplitude: 0.2,
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::autoplay::AutoplayPlan;
+use crate::components::*;
+use input::Hand;
+use nalgebra::{Isometry3, Translation3, UnitQuaternion, Vector3};
+
+// Metres per second the head moves to dodge walls
+const HEAD_SPEED: f32 = 4.0;
+
+/// Moves the sabers and the head along the autoplay plan in place of the controllers
+#[derive(Default)]
+pub struct AutoplaySystem {
+    head: Option<Vector3<f32>>,
+    last_time: Option<f32>,
+}
+
+impl<'a> specs::System<'a> for AutoplaySystem {
+    type SystemData = (
+        specs::Read<'a, clock::SongClock>,
+        specs::Read<'a, AutoplayPlan>,
+        specs::Read<'a, lane::ActiveLane>,
+        specs::Write<'a, input::TrackedPoses>,
+    );
+
+    fn run(&mut self, (clock, plan, lane, mut poses): Self::SystemData) {
+        let time = clock.time();
+        let frame_ms = self
+            .last_time
+            .map(|last_time| (time - last_time).max(0.0))
+            .unwrap_or(0.0);
+        self.last_time = Some(time);
+
+        // The clock moves on before the sabers are tested against notes
+        let saber_time = time + frame_ms;
+        for hand in &[Hand::Left, Hand::Right] {
+            let grip = plan.grip(*hand, saber_time);
+            let input = &mut poses.hands[*hand as usize];
+            input.grip = Some(grip);
+            input.aim = Some(grip);
+            input.trigger = 0.0;
+        }
+
+        let target = plan.head(time, lane.degrees);
+        let head = match self.head {
+            Some(head) => {
+                let step = target - head;
+                let max_step = HEAD_SPEED * frame_ms / 1000.0;
+                if step.norm() > max_step {
+                    head + step.normalize() * max_step
+                } else {
+                    target
+                }
+            }
+            None => target,
+        };
+        self.head = Some(head);
+        poses.head = Some(Isometry3::from_parts(
+            Translation3::from(head),
+            lane.rotation()
+                * UnitQuaternion::from_axis_angle(&Vector3::y_axis(), std::f32::consts::PI),
+        ));
+    }
+}
@@ -0,0 +1,188 @@
+use crate::components::*;
+use std::time::{Duration, Instant};
+
+pub const SONG_SOUND: &str = "SongPlayback";
+
+pub enum ClockDriver {
+    // Follows the wall clock from the moment the song audio started playing
+    Realtime,
+    // Time only changes through `set_time` and `advance`, for tests and offline runs
+    Manual,
+}
+
+/// Song time shared by every gameplay system. It is sampled once per frame,
+/// so all objects are positioned against the same moment
+pub struct SongClock {
+    pub driver: ClockDriver,
+    // Song time that passes in one second, changed by the song speed modifiers
+    pub speed: f32,
+    // Milliseconds since the beginning of the song
+    time: f32,
+    started_at: Option<Instant>,
+    paused_at: Option<Instant>,
+}
+
+impl Default for SongClock {
+    fn default() -> Self {
+        Self {
+            driver: ClockDriver::Realtime,
+            speed: 1.0,
+            time: 0.0,
+            started_at: None,
+            paused_at: None,
+        }
+    }
+}
+
+impl SongClock {
+    pub fn manual() -> Self {
+        Self {
+            driver: ClockDriver::Manual,
+            ..Default::default()
+        }
+    }
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+    /// Anchors song time 0 to the moment the audio started
+    pub fn start(&mut self, at: Instant) {
+        self.started_at = Some(at);
+        self.paused_at = None;
+        self.time = 0.0;
+    }
+    /// Counts up from `-ms` so objects are already flying in when the audio starts
+    pub fn start_countdown(&mut self, at: Instant, ms: f32) {
+        self.started_at = Some(at + Duration::from_micros((ms * 1000.0) as u64));
+        self.paused_at = None;
+        self.time = -ms * self.speed;
+    }
+    pub fn pause(&mut self, at: Instant) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(at);
+        }
+    }
+    pub fn resume(&mut self, at: Instant) {
+        if let (Some(started_at), Some(paused_at)) = (self.started_at, self.paused_at.take()) {
+            self.started_at = Some(started_at + (at - paused_at));
+        }
+    }
+    pub fn set_time(&mut self, time: f32) {
+        self.time = time;
+    }
+    pub fn advance(&mut self, ms: f32) {
+        self.time += ms;
+    }
+    pub fn update(&mut self, now: Instant, latency_ms: f32) {
+        if let ClockDriver::Realtime = self.driver {
+            if let Some(started_at) = self.started_at {
+                let now = self.paused_at.unwrap_or(now);
+                let elapsed = if now > started_at {
+                    (now - started_at).as_micros() as f32 / 1000.0
+                } else {
+                    -((started_at - now).as_micros() as f32 / 1000.0)
+                };
+                self.time = (elapsed - latency_ms) * self.speed;
+            }
+        }
+    }
+}
+
+pub struct SongClockSystem;
+
+impl<'a> specs::System<'a> for SongClockSystem {
+    type SystemData = (
+        specs::Write<'a, SongClock>,
+        specs::Write<'a, sound::SoundEvents>,
+        specs::Read<'a, PlayerSettings>,
+    );
+
+    fn run(&mut self, (mut clock, mut sounds, settings): Self::SystemData) {
+        for (name, started_at) in sounds.started.drain(..) {
+            if name == SONG_SOUND {
+                clock.start(started_at);
+            }
+        }
+        clock.update(Instant::now(), settings.audio_latency_ms);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    fn assert_time(clock: &SongClock, time: f32) {
+        assert!((clock.time() - time).abs() < 0.01, "{} != {}", clock.time(), time);
+    }
+
+    #[test]
+    fn manual_clock_only_moves_when_driven() {
+        let mut clock = SongClock::manual();
+        clock.start_countdown(Instant::now(), 2000.0);
+        assert_time(&clock, -2000.0);
+        clock.update(Instant::now() + ms(5000), 0.0);
+        assert_time(&clock, -2000.0);
+        clock.advance(2500.0);
+        assert_time(&clock, 500.0);
+        clock.set_time(100.0);
+        assert_time(&clock, 100.0);
+    }
+
+    #[test]
+    fn countdown_counts_up_to_the_audio_start() {
+        let start = Instant::now();
+        let mut clock = SongClock::default();
+        clock.start_countdown(start, 3000.0);
+        clock.update(start + ms(1000), 0.0);
+        assert_time(&clock, -2000.0);
+        clock.update(start + ms(3500), 0.0);
+        assert_time(&clock, 500.0);
+    }
+
+    #[test]
+    fn countdown_runs_at_song_speed() {
+        let start = Instant::now();
+        let mut clock = SongClock {
+            speed: 1.5,
+            ..Default::default()
+        };
+        clock.start_countdown(start, 2000.0);
+        assert_time(&clock, -3000.0);
+        clock.update(start + ms(3000), 0.0);
+        assert_time(&clock, 1500.0);
+    }
+
+    #[test]
+    fn pause_stops_time_and_resume_continues_from_there() {
+        let start = Instant::now();
+        let mut clock = SongClock::default();
+        clock.start(start);
+        clock.pause(start + ms(1000));
+        clock.update(start + ms(4000), 0.0);
+        assert_time(&clock, 1000.0);
+        // Pausing again doesn't move the moment of the pause
+        clock.pause(start + ms(3000));
+        clock.resume(start + ms(4000));
+        clock.update(start + ms(4500), 0.0);
+        assert_time(&clock, 1500.0);
+    }
+
+    #[test]
+    fn late_audio_start_corrects_the_clock() {
+        let start = Instant::now();
+        let mut clock = SongClock::default();
+        clock.start_countdown(start, 1000.0);
+        clock.update(start + ms(1020), 0.0);
+        assert_time(&clock, 20.0);
+        // The audio only started 40 ms after the end of the countdown
+        clock.start(start + ms(1040));
+        clock.update(start + ms(1050), 0.0);
+        assert_time(&clock, 10.0);
+        // Audio reaches the ears later than it starts playing
+        clock.update(start + ms(1100), 30.0);
+        assert_time(&clock, 30.0);
+    }
+}
@@ -6,6 +6,8 @@ pub struct Drawable {
     pub texture: String,
     pub shader: String,
     pub enabled: bool,
+    // Multiplies the colour of the object, the wall shader draws only this colour
+    pub tint: [f32; 4],
 }
 
 impl Drawable {
@@ -15,6 +17,7 @@ impl Drawable {
             texture,
             shader,
             enabled: true,
+            tint: [1.0, 1.0, 1.0, 1.0],
         }
     }
 }
@@ -0,0 +1,235 @@
+use crate::collision::{self, Obb};
+use crate::components::*;
+use crate::scoring::ScoringType;
+use input::Hand;
+use nalgebra::Point3;
+use note::{Direction, NoteType};
+use specs::Join;
+
+// Notes can still be cut this far behind the hit plane
+pub const MISS_DISTANCE: f32 = 0.5;
+
+#[derive(Clone, Copy, Debug)]
+pub struct CutInfo {
+    pub hand: Hand,
+    pub scoring: ScoringType,
+    pub wrong_color: bool,
+    pub wrong_direction: bool,
+    pub cut: collision::Cut,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum GameplayEvent {
+    GoodCut(CutInfo),
+    BadCut(CutInfo),
+    Miss { scoring: ScoringType },
+    MineHit,
+    // Sent every frame the head is inside a wall, `entered` is set on the first one
+    WallContact { duration_ms: f32, entered: bool },
+}
+
+/// Judgements of the current frame, cleared in `maintain`
+#[derive(Default)]
+pub struct GameplayEvents {
+    pub queue: Vec<GameplayEvent>,
+}
+
+pub fn note_hand(note_type: &NoteType) -> Option<Hand> {
+    match note_type {
+        NoteType::Red => Some(Hand::Left),
+        NoteType::Blue => Some(Hand::Right),
+        NoteType::Mine => None,
+    }
+}
+
+/// Tests notes against the sabers and judges notes that were cut or passed the player
+pub struct HitSystem;
+
+impl<'a> specs::System<'a> for HitSystem {
+    type SystemData = (
+        specs::Entities<'a>,
+        specs::Read<'a, CurrentSongInfo>,
+        specs::Read<'a, clock::SongClock>,
+        specs::Read<'a, crate::modifiers::Modifiers>,
+        specs::Write<'a, GameplayEvents>,
+        specs::Write<'a, RemoveEntities>,
+        specs::Write<'a, sound::SoundEvents>,
+        specs::Write<'a, input::HapticEvents>,
+        specs::ReadStorage<'a, saber::Saber>,
+        specs::ReadStorage<'a, transform::Transform>,
+        specs::ReadStorage<'a, note::Note>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            ents,
+            song_info,
+            clock,
+            modifiers,
+            mut events,
+            mut ents_to_remove,
+            mut sounds,
+            mut haptics,
+            sabers,
+            transforms,
+            notes,
+        ): Self::SystemData,
+    ) {
+        let tolerance = if modifiers.strict_angles {
+            collision::STRICT_CUT_ANGLE_TOLERANCE
+        } else {
+            collision::CUT_ANGLE_TOLERANCE
+        };
+        for (ent, transform, note) in (&ents, &transforms, &notes).join() {
+            // Distance along the lane of the note, its transform may be turned around the player
+            let distance = song_info.note_jump.distance(note.time - clock.time()) + HIT_PLANE_Z;
+            let obb = Obb {
+                center: Point3::from(transform.position.vector),
+                rotation: transform.rotation,
+                half_extents: transform.scale,
+            };
+            let hit =
+                (&sabers)
+                    .join()
+                    .find_map(|saber| match (saber.previous_blade, saber.blade) {
+                        (Some(from), Some(to)) => {
+                            collision::cut(&from, &to, &obb).map(|cut| (saber.hand, cut))
+                        }
+                        _ => None,
+                    });
+
+            match (hit, note_hand(&note.note_type)) {
+                (Some((hand, _)), None) => {
+                    events.queue.push(GameplayEvent::MineHit);
+                    haptics.queue.push(input::HapticEvent {
+                        hand,
+                        duration_ms: 150.0,
+                        amplitude: 1.0,
+                    });
+                    ents_to_remove.0.push(ent);
+                }
+                (Some((hand, cut)), Some(note_hand)) => {
+                    let wrong_direction = match note.direction {
+                        Direction::NoDirection => false,
+                        _ => !collision::direction_matches(
+                            &cut.direction,
+                            &transform.rotation,
+                            tolerance,
+                        ),
+                    };
+                    let cut_info = CutInfo {
+                        hand,
+                        scoring: note.scoring,
+                        wrong_color: hand != note_hand,
+                        wrong_direction,
+                        cut,
+                    };
+                    if cut_info.wrong_color || cut_info.wrong_direction {
+                        events.queue.push(GameplayEvent::BadCut(cut_info));
+                    } else {
+                        events.queue.push(GameplayEvent::GoodCut(cut_info));
+                        sounds.queue.push(sound::SoundEvent::AddSound(
+                            "./assets/sounds/slash.mp3".to_string(),
+                            None,
+                        ));
+                    }
+                    haptics.queue.push(input::HapticEvent {
+                        hand,
+                        duration_ms: 50.0,
+                        amplitude: 0.7,
+                    });
+                    ents_to_remove.0.push(ent);
+                }
+                // Mines fly past the player until the end of their jump
+                (None, None) => {
+                    if distance < HIT_PLANE_Z - song_info.note_jump.spawn_distance() {
+                        ents_to_remove.0.push(ent);
+                    }
+                }
+                (None, Some(_)) => {
+                    if distance < HIT_PLANE_Z - MISS_DISTANCE {
+                        events.queue.push(GameplayEvent::Miss {
+                            scoring: note.scoring,
+                        });
+                        ents_to_remove.0.push(ent);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collision::Blade;
+    use nalgebra::{Translation3, UnitQuaternion, Vector3};
+    use specs::{Builder, RunNow};
+
+    fn world_with_red_note() -> specs::World {
+        let mut world = specs::World::new();
+        register_default(&mut world);
+        world
+            .create_entity()
+            .with(note::Note {
+                line_layer: 0,
+                line_index: 1,
+                note_type: NoteType::Red,
+                time: 0.0,
+                direction: Direction::Top,
+                angle_offset: 0,
+                rotation: 0.0,
+                scoring: ScoringType::Normal,
+                link: None,
+            })
+            .with(transform::Transform::new(
+                Translation3::new(0.0, 1.0, HIT_PLANE_Z),
+                UnitQuaternion::identity(),
+                Vector3::new(0.15, 0.15, 0.15),
+            ))
+            .build();
+        world
+    }
+
+    // Swings the saber of `hand` up through the note and judges it
+    fn swing_up(world: &mut specs::World, hand: Hand) -> Vec<GameplayEvent> {
+        let blade = |y| Blade {
+            base: Point3::new(0.0, y, 0.0),
+            tip: Point3::new(0.0, y, 1.5),
+        };
+        world
+            .create_entity()
+            .with(saber::Saber {
+                hand,
+                previous_blade: Some(blade(0.6)),
+                blade: Some(blade(1.4)),
+                swing: Default::default(),
+            })
+            .build();
+        HitSystem.run_now(&world.res);
+        world.read_resource::<GameplayEvents>().queue.clone()
+    }
+
+    #[test]
+    fn matching_saber_cuts_the_note() {
+        let mut world = world_with_red_note();
+        match swing_up(&mut world, Hand::Left)[..] {
+            [GameplayEvent::GoodCut(cut)] => assert_eq!(cut.hand, Hand::Left),
+            ref events => panic!("expected a good cut, got {:?}", events),
+        }
+        assert_eq!(world.read_resource::<RemoveEntities>().0.len(), 1);
+    }
+
+    #[test]
+    fn other_saber_makes_a_wrong_colour_cut() {
+        let mut world = world_with_red_note();
+        match swing_up(&mut world, Hand::Right)[..] {
+            [GameplayEvent::BadCut(cut)] => {
+                assert!(cut.wrong_color);
+                assert!(!cut.wrong_direction);
+            }
+            ref events => panic!("expected a bad cut, got {:?}", events),
+        }
+    }
+}
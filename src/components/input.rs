@@ -0,0 +1,45 @@
+use nalgebra::Isometry3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Hand {
+    Left = 0,
+    Right = 1,
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct HandInput {
+    // None while the controller isn't tracked
+    pub grip: Option<Isometry3<f32>>,
+    pub aim: Option<Isometry3<f32>>,
+    pub trigger: f32,
+    // Only true in the frame the button went down
+    pub menu_pressed: bool,
+}
+
+/// Head and controllers of the current frame in playfield coordinates
+#[derive(Default)]
+pub struct TrackedPoses {
+    pub head: Option<Isometry3<f32>>,
+    pub hands: [HandInput; 2],
+}
+
+impl TrackedPoses {
+    pub fn hand(&self, hand: Hand) -> &HandInput {
+        &self.hands[hand as usize]
+    }
+    pub fn menu_pressed(&self) -> bool {
+        self.hands.iter().any(|hand| hand.menu_pressed)
+    }
+}
+
+pub struct HapticEvent {
+    pub hand: Hand,
+    pub duration_ms: f32,
+    // 0.0 to 1.0
+    pub amplitude: f32,
+}
+
+#[derive(Default)]
+pub struct HapticEvents {
+    pub queue: Vec<HapticEvent>,
+}
@@ -0,0 +1,51 @@
+use crate::components::*;
+use crate::lanes::lane_rotation;
+use nalgebra::UnitQuaternion;
+
+// Degrees per second the environment turns to a new lane
+const TURN_SPEED: f32 = 90.0;
+
+/// Lane the player is facing, the environment turns along with it
+#[derive(Default)]
+pub struct ActiveLane {
+    // Degrees, see `lanes::RotationEvent`
+    pub degrees: f32,
+}
+
+impl ActiveLane {
+    pub fn rotation(&self) -> UnitQuaternion<f32> {
+        lane_rotation(self.degrees)
+    }
+}
+
+/// Follows the rotation events of the song against song time
+#[derive(Default)]
+pub struct LaneSystem {
+    last_time: Option<f32>,
+}
+
+impl<'a> specs::System<'a> for LaneSystem {
+    type SystemData = (
+        specs::Read<'a, CurrentSongInfo>,
+        specs::Read<'a, clock::SongClock>,
+        specs::Write<'a, ActiveLane>,
+    );
+
+    fn run(&mut self, (song_info, clock, mut lane): Self::SystemData) {
+        let time = clock.time();
+        let elapsed = self
+            .last_time
+            .map(|last_time| (time - last_time).max(0.0))
+            .unwrap_or(0.0);
+        self.last_time = Some(time);
+
+        let target = song_info.lane_rotations.rotation_at(time);
+        let step = target - lane.degrees;
+        let max_step = TURN_SPEED * elapsed / 1000.0;
+        lane.degrees = if step.abs() > max_step {
+            lane.degrees + max_step.copysign(step)
+        } else {
+            target
+        };
+    }
+}
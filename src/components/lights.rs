@@ -0,0 +1,246 @@
+use crate::components::*;
+use crate::lighting::*;
+use nalgebra::{Isometry3, Translation3, UnitQuaternion, Vector3};
+use specs::{Builder, Component, Join, VecStorage};
+use std::collections::VecDeque;
+
+const RING_COUNT: usize = 10;
+// First ring in front of the player and the size of the square the rings make
+const RING_START_Z: f32 = 12.0;
+const RING_HALF_SIZE: f32 = 7.0;
+const RING_HEIGHT: f32 = 2.0;
+const LASERS_PER_SIDE: usize = 4;
+
+/// Light events of the song that is played, ordered by time
+#[derive(Default)]
+pub struct LightEvents {
+    pub queue: VecDeque<LightEvent>,
+}
+
+impl LightEvents {
+    pub fn new(mut events: Vec<LightEvent>) -> Self {
+        events.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Self {
+            queue: events.into(),
+        }
+    }
+}
+
+/// State of the environment lights, also read by the renderer for the light uniform
+pub struct EnvironmentLights {
+    pub groups: [LightGroup; LIGHT_GROUPS],
+    pub rings: Rings,
+    pub lasers: [Lasers; 2],
+    // Red and blue light colours
+    pub colors: [[f32; 3]; 2],
+    // Average colour of every light, with the intensity already applied
+    pub ambient: [f32; 4],
+}
+
+impl Default for EnvironmentLights {
+    fn default() -> Self {
+        Self::new([LIGHT_RED, LIGHT_BLUE])
+    }
+}
+
+impl EnvironmentLights {
+    /// Lights with every group on in the blue colour
+    pub fn new(colors: [[f32; 3]; 2]) -> Self {
+        Self {
+            groups: [LightGroup::new(colors[1]); LIGHT_GROUPS],
+            rings: Default::default(),
+            lasers: Default::default(),
+            colors,
+            ambient: [0.0, 0.0, 0.0, 1.0],
+        }
+    }
+
+    pub fn apply(&mut self, event: &LightEvent) {
+        match event.kind {
+            LightEventKind::Light(group) => self.groups[group].apply(event, &self.colors),
+            LightEventKind::RingRotation => self.rings.rotate(event.time),
+            LightEventKind::RingZoom => self.rings.zoom(event.time),
+            LightEventKind::LeftLaserSpeed => self.lasers[0].set_speed(event.value),
+            LightEventKind::RightLaserSpeed => self.lasers[1].set_speed(event.value),
+        }
+    }
+
+    fn update_ambient(&mut self, time: f32) {
+        let mut ambient = [0.0, 0.0, 0.0, 1.0];
+        for group in &self.groups {
+            let [r, g, b, intensity] = group.color(time);
+            for (channel, value) in ambient.iter_mut().zip([r, g, b].iter()) {
+                *channel += value * intensity / LIGHT_GROUPS as f32;
+            }
+        }
+        self.ambient = ambient;
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum LightMotion {
+    Fixed,
+    // Index of the ring the light is part of
+    Ring(usize),
+    // Side of the laser, 0 for left and 1 for right
+    Laser(usize),
+}
+
+/// Part of the environment that lights up with its light group
+#[derive(Component)]
+#[storage(VecStorage)]
+pub struct Light {
+    pub group: usize,
+    pub motion: LightMotion,
+    // Placement relative to the ring centre or the laser pivot, or in the world when fixed
+    pub placement: Isometry3<f32>,
+}
+
+fn create_light(
+    world: &mut specs::World,
+    group: usize,
+    motion: LightMotion,
+    placement: Isometry3<f32>,
+    scale: Vector3<f32>,
+) {
+    world
+        .create_entity()
+        .with(transform::Transform::new(
+            placement.translation,
+            placement.rotation,
+            scale,
+        ))
+        .with(drawable::Drawable::new(
+            "cube".to_string(),
+            "dev".to_string(),
+            "light".to_string(),
+        ))
+        .with(Light {
+            group,
+            motion,
+            placement,
+        })
+        .build();
+}
+
+/// Back lasers, rings, side lasers and the lights along the track
+pub fn create_environment(world: &mut specs::World) {
+    for i in 0..5 {
+        let angle = (i as f32 - 2.0) * 0.35;
+        create_light(
+            world,
+            0,
+            LightMotion::Fixed,
+            Isometry3::from_parts(
+                Translation3::new(0.0, 6.0, 60.0),
+                UnitQuaternion::from_euler_angles(0.0, 0.0, angle),
+            ),
+            Vector3::new(0.08, 8.0, 0.08),
+        );
+    }
+    for ring in 0..RING_COUNT {
+        for side in 0..4 {
+            let rotation = UnitQuaternion::from_euler_angles(
+                0.0,
+                0.0,
+                side as f32 * std::f32::consts::FRAC_PI_2,
+            );
+            create_light(
+                world,
+                1,
+                LightMotion::Ring(ring),
+                Isometry3::from_parts(
+                    Translation3::from(rotation * Vector3::new(0.0, RING_HALF_SIZE, 0.0)),
+                    rotation,
+                ),
+                Vector3::new(RING_HALF_SIZE, 0.06, 0.06),
+            );
+        }
+    }
+    for (side, x) in [10.0, -10.0].iter().enumerate() {
+        for i in 0..LASERS_PER_SIDE {
+            create_light(
+                world,
+                2 + side,
+                LightMotion::Laser(side),
+                Isometry3::translation(*x, 0.0, 20.0 + i as f32 * 8.0),
+                Vector3::new(0.05, 15.0, 0.05),
+            );
+        }
+    }
+    for x in &[1.6, -1.6] {
+        create_light(
+            world,
+            4,
+            LightMotion::Fixed,
+            Isometry3::translation(*x, 0.0, 25.0),
+            Vector3::new(0.04, 0.04, 20.0),
+        );
+    }
+}
+
+/// Plays the light events of the song against song time and moves the lights
+#[derive(Default)]
+pub struct EnvironmentLightsSystem {
+    last_time: Option<f32>,
+}
+
+impl<'a> specs::System<'a> for EnvironmentLightsSystem {
+    type SystemData = (
+        specs::Read<'a, clock::SongClock>,
+        specs::Write<'a, LightEvents>,
+        specs::Write<'a, EnvironmentLights>,
+        specs::Read<'a, lane::ActiveLane>,
+        specs::ReadStorage<'a, Light>,
+        specs::WriteStorage<'a, transform::Transform>,
+        specs::WriteStorage<'a, drawable::Drawable>,
+    );
+
+    fn run(
+        &mut self,
+        (clock, mut events, mut environment, lane, lights, mut transforms, mut drawables): Self::SystemData,
+    ) {
+        let time = clock.time();
+        let elapsed = self
+            .last_time
+            .map(|last_time| (time - last_time).max(0.0))
+            .unwrap_or(0.0);
+        self.last_time = Some(time);
+
+        while events.queue.front().map(|event| event.time <= time) == Some(true) {
+            let event = events.queue.pop_front().unwrap();
+            environment.apply(&event);
+        }
+        for lasers in environment.lasers.iter_mut() {
+            lasers.update(elapsed);
+        }
+        environment.rings.settle(time, RING_COUNT);
+        environment.update_ambient(time);
+
+        let spacing = environment.rings.spacing(time);
+        // The environment is built around the lane the player faces
+        let lane = Isometry3::from_parts(Translation3::identity(), lane.rotation());
+        for (light, transform, drawable) in (&lights, &mut transforms, &mut drawables).join() {
+            drawable.tint = environment.groups[light.group].color(time);
+            let pose = match light.motion {
+                LightMotion::Fixed => light.placement,
+                LightMotion::Ring(ring) => {
+                    let angle = environment.rings.angle(ring, time).to_radians();
+                    Isometry3::from_parts(
+                        Translation3::new(0.0, RING_HEIGHT, RING_START_Z + ring as f32 * spacing),
+                        UnitQuaternion::from_euler_angles(0.0, 0.0, angle),
+                    ) * light.placement
+                }
+                LightMotion::Laser(side) => {
+                    // Left and right lasers turn in opposite directions
+                    let angle = environment.lasers[side].angle.to_radians();
+                    let angle = if side == 0 { angle } else { -angle };
+                    light.placement * Isometry3::rotation(Vector3::new(0.0, 0.0, angle + 0.4))
+                }
+            };
+            let pose = lane * pose;
+            transform.position = pose.translation;
+            transform.rotation = pose.rotation;
+        }
+    }
+}
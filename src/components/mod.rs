@@ -1,14 +1,33 @@
+pub mod arc;
+pub mod autoplay;
+pub mod clock;
 pub mod drawable;
+pub mod hit;
+pub mod input;
+pub mod lane;
+pub mod lights;
 pub mod note;
 pub mod obstacle;
+pub mod replay;
+pub mod saber;
+pub mod score;
 pub mod sound;
+pub mod spawn;
+pub mod stats;
 pub mod transform;
 
+/// Distance in front of the player where objects arrive at their time
+pub const HIT_PLANE_Z: f32 = 1.0;
+
 pub fn register_default(world: &mut specs::World) {
     world.register::<note::Note>();
     world.register::<obstacle::Obstacle>();
     world.register::<transform::Transform>();
     world.register::<drawable::Drawable>();
+    world.register::<saber::Saber>();
+    world.register::<lights::Light>();
+    world.register::<arc::Arc>();
+    world.register::<arc::Curve>();
 
     world.add_resource(CurrentSongInfo {
         ..Default::default()
@@ -19,13 +38,88 @@ pub fn register_default(world: &mut specs::World) {
     world.add_resource(RemoveEntities {
         ..Default::default()
     });
+    world.add_resource(PlayerSettings {
+        ..Default::default()
+    });
+    world.add_resource(clock::SongClock::default());
+    world.add_resource(spawn::SpawnQueue {
+        ..Default::default()
+    });
+    world.add_resource(input::TrackedPoses {
+        ..Default::default()
+    });
+    world.add_resource(input::HapticEvents {
+        ..Default::default()
+    });
+    world.add_resource(hit::GameplayEvents {
+        ..Default::default()
+    });
+    world.add_resource(score::SongScore {
+        ..Default::default()
+    });
+    world.add_resource(stats::PlayerStats::default());
+    world.add_resource(crate::modifiers::Modifiers {
+        ..Default::default()
+    });
+    world.add_resource(crate::autoplay::AutoplayPlan::default());
+    world.add_resource(replay::ReplayRecording {
+        ..Default::default()
+    });
+    world.add_resource(replay::ReplayPlayback {
+        ..Default::default()
+    });
+    world.add_resource(lights::LightEvents {
+        ..Default::default()
+    });
+    world.add_resource(lights::EnvironmentLights::default());
+    world.add_resource(crate::colors::ColorScheme::default());
+    world.add_resource(lane::ActiveLane::default());
+    world.add_resource(obstacle::HeadInWall::default());
+}
+
+/// Applies entity changes queued during the frame
+pub fn maintain(world: &mut specs::World) {
+    world.maintain();
+    world.write_resource::<hit::GameplayEvents>().queue.clear();
+    let ents_to_remove = {
+        let ents_to_remove_raw = &mut world.write_resource::<RemoveEntities>().0;
+        let mut ents_to_remove = ents_to_remove_raw.clone();
+        ents_to_remove_raw.clear();
+        ents_to_remove.sort();
+        ents_to_remove.dedup();
+        ents_to_remove
+    };
+    world.delete_entities(&ents_to_remove).unwrap();
 }
 
 #[derive(Default)]
 pub struct CurrentSongInfo {
-    pub bpm: f32,
-    pub bpb: f32,
-    pub time: i32,
+    pub tempo_map: crate::timing::TempoMap,
+    pub note_jump: crate::timing::NoteJump,
+    // Path of the audio file
+    pub song_file: String,
+    pub map_hash: String,
+    // Folder the song was loaded from and the names its info file gives the difficulty,
+    // whatever spelling it was selected with
+    pub folder: String,
+    pub characteristic: String,
+    pub difficulty: String,
+    pub lane_rotations: crate::lanes::LaneRotations,
+}
+
+#[derive(Default)]
+pub struct PlayerSettings {
+    // Replaces the note jump speed of every map if set
+    pub note_jump_speed: Option<f32>,
+    // Time between handing audio to the output device and hearing it
+    pub audio_latency_ms: f32,
+    // The autoplay bot moves the sabers instead of the controllers
+    pub autoplay: bool,
+    // Folders searched for songs, the default one if empty
+    pub song_dirs: Vec<std::path::PathBuf>,
+    // Preset picked by the player, maps can replace some of its colours
+    pub color_scheme: crate::colors::ColorScheme,
+    pub ignore_map_colors: bool,
 }
 
 #[derive(Default)]
@@ -1,6 +1,7 @@
 use crate::components::*;
 use specs::{Component, Join, VecStorage};
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(i32)]
 pub enum Direction {
     Top = 0,
@@ -17,6 +18,7 @@ pub enum Direction {
     NoDirection = 8,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(i32)]
 #[warn(unused_imports)]
 pub enum NoteType {
@@ -34,59 +36,64 @@ pub struct Note {
     pub note_type: NoteType,
     pub time: f32,
     pub direction: Direction,
+    // Counter-clockwise rotation of the cut direction in degrees (v3 only)
+    pub angle_offset: i32,
+    // Degrees the lane of the note is turned around the player, see `lanes`
+    pub rotation: f32,
+    pub scoring: crate::scoring::ScoringType,
+    // Only set for the links of chains, which sit between grid cells
+    pub link: Option<ChainLink>,
 }
 
-#[derive(Default)]
-pub struct NoteSystem {
-    pub last_update_time_ms: u128,
+#[derive(Clone, Copy, Debug)]
+pub struct ChainLink {
+    // Grid position like `line_index` and `line_layer`
+    pub x: f32,
+    pub y: f32,
+    // Roll of the link in degrees, it faces along the chain
+    pub angle: f32,
 }
 
+#[derive(Default)]
+pub struct NoteSystem;
+
 impl<'a> specs::System<'a> for NoteSystem {
     type SystemData = (
-        specs::Entities<'a>,
-        specs::Write<'a, RemoveEntities>,
-        specs::Write<'a, sound::SoundEvents>,
-        specs::WriteStorage<'a, drawable::Drawable>,
+        specs::Read<'a, CurrentSongInfo>,
+        specs::Read<'a, clock::SongClock>,
+        specs::Read<'a, crate::modifiers::Modifiers>,
         specs::WriteStorage<'a, transform::Transform>,
+        specs::WriteStorage<'a, drawable::Drawable>,
         specs::ReadStorage<'a, Note>,
     );
 
     fn run(
         &mut self,
-        (ents, mut ents_to_remove, mut sounds, mut drawables, mut transforms, notes): Self::SystemData,
+        (song_info, clock, modifiers, mut transforms, mut drawables, notes): Self::SystemData,
     ) {
-        let sys_time = std::time::SystemTime::now();
-        let current_time = sys_time
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis();
-        if self.last_update_time_ms != 0 {
-            let time_diff = (current_time - self.last_update_time_ms) as f32;
-            for (ent, transform, note, drawable) in
-                (&ents, &mut transforms, &notes, &mut drawables).join()
-            {
-                transform.position.vector =
-                    transform.position.vector - nalgebra::Vector3::new(0.0, 0.0, time_diff / 60.0);
-                let (remove_position, sound_enabled) = match note.note_type {
-                    NoteType::Mine => (-5.0, false),
-                    _ => (5.0, true),
-                };
-                if transform.position.z < remove_position {
-                    ents_to_remove.0.push(ent);
-                    if sound_enabled {
-                        sounds.queue.push(sound::SoundEvent::AddSound(
-                            "./assets/sounds/slash.mp3".to_string(),
-                            None,
-                        ));
-                    }
+        let note_jump = &song_info.note_jump;
+        // Disappearing arrows and ghost notes take effect in the second half of the jump
+        let hide_position = HIT_PLANE_Z + note_jump.spawn_distance() / 2.0;
+        for (transform, drawable, note) in (&mut transforms, &mut drawables, &notes).join() {
+            let position = crate::songs::note_position(note);
+            let distance = note_jump.distance(note.time - clock.time()) + HIT_PLANE_Z;
+            transform.position =
+                crate::songs::lane_position(note.rotation, position.x, position.y, distance);
+            if distance > hide_position {
+                continue;
+            }
+            match note.note_type {
+                NoteType::Mine => {}
+                // The arrow is part of the note texture, so ghost notes hide the whole note
+                _ if modifiers.ghost_notes => drawable.enabled = false,
+                NoteType::Red if modifiers.disappearing_arrows => {
+                    drawable.texture = "note_middle_red".to_string()
                 }
-                if transform.position.z > 40.0 {
-                    drawable.enabled = false;
-                } else {
-                    drawable.enabled = true;
+                NoteType::Blue if modifiers.disappearing_arrows => {
+                    drawable.texture = "note_middle_blue".to_string()
                 }
+                _ => {}
             }
         }
-        self.last_update_time_ms = current_time;
     }
 }
@@ -1,55 +1,101 @@
 use crate::components::*;
 use specs::{Component, Join, VecStorage};
 
-pub enum ObstacleType {
-    Wall = 0,
-    Ceiling = 1
-}
-
 #[derive(Component)]
 #[storage(VecStorage)]
 pub struct Obstacle {
     pub line_index: i32,
-    pub obstacle_type: ObstacleType,
+    pub line_layer: i32,
     pub width: i32,
+    pub height: i32,
     pub time: f32,
     pub duration: f32,
+    // Degrees the lane of the obstacle is turned around the player, see `lanes`
+    pub rotation: f32,
+}
+
+const WALL_ALPHA: f32 = 0.7;
+// Walls light up while the head is inside them
+pub const WALL_WARNING_COLOR: [f32; 4] = [1.0, 0.9, 0.3, 0.9];
+
+pub fn wall_tint(colors: &crate::colors::ColorScheme) -> [f32; 4] {
+    let [r, g, b] = colors.obstacles;
+    [r, g, b, WALL_ALPHA]
 }
 
+/// Whether the head was in a wall on the last frame of the current song and when that was
 #[derive(Default)]
-pub struct ObstacleSystem {
-    pub last_update_time_ms: u128,
+pub struct HeadInWall {
+    pub inside: bool,
+    pub last_time: Option<f32>,
 }
 
+pub struct ObstacleSystem;
+
 impl<'a> specs::System<'a> for ObstacleSystem {
     type SystemData = (
         specs::Entities<'a>,
+        specs::Read<'a, CurrentSongInfo>,
+        specs::Read<'a, clock::SongClock>,
+        specs::Read<'a, input::TrackedPoses>,
+        specs::Read<'a, crate::colors::ColorScheme>,
         specs::Write<'a, RemoveEntities>,
+        specs::Write<'a, hit::GameplayEvents>,
+        specs::Write<'a, HeadInWall>,
         specs::WriteStorage<'a, transform::Transform>,
+        specs::WriteStorage<'a, drawable::Drawable>,
         specs::ReadStorage<'a, Obstacle>,
     );
 
     fn run(
         &mut self,
-        (ents, mut ents_to_remove, mut transforms, obstacles): Self::SystemData,
+        (ents, song_info, clock, poses, colors, mut ents_to_remove, mut events, mut head_in_wall_state, mut transforms, mut drawables, obstacles): Self::SystemData,
     ) {
-        let sys_time = std::time::SystemTime::now();
-        let current_time = sys_time
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis();
-        if self.last_update_time_ms != 0 {
-            let time_diff = (current_time - self.last_update_time_ms) as f32;
-            for (ent, transform, obstacle) in
-                (&ents, &mut transforms, &obstacles).join()
-            {
-                transform.position.vector =
-                    transform.position.vector - nalgebra::Vector3::new(0.0, 0.0, time_diff / 60.0);
-                if transform.position.z < -obstacle.duration {
-                    ents_to_remove.0.push(ent);
-                }
+        let note_jump = &song_info.note_jump;
+        let head = poses.head.map(|head| head.translation.vector);
+        let mut head_in_wall = false;
+        for (ent, transform, drawable, obstacle) in
+            (&ents, &mut transforms, &mut drawables, &obstacles).join()
+        {
+            let length = note_jump.distance(obstacle.duration);
+            let distance =
+                note_jump.distance(obstacle.time - clock.time()) + HIT_PLANE_Z + length / 2.0;
+            if distance + length / 2.0 < HIT_PLANE_Z - note_jump.spawn_distance() {
+                ents_to_remove.0.push(ent);
             }
+
+            let (center, half_size) = crate::songs::obstacle_footprint(obstacle);
+            transform.position =
+                crate::songs::lane_position(obstacle.rotation, center.x, center.y, distance);
+            let inside = head
+                .map(|head| {
+                    // The head in the space of the lane of the obstacle
+                    let head = crate::lanes::lane_rotation(obstacle.rotation).inverse() * head;
+                    (head.x - center.x).abs() < half_size.x.abs()
+                        && (head.y - center.y).abs() < half_size.y.abs()
+                        && (head.z - distance).abs() < length / 2.0
+                })
+                .unwrap_or(false);
+            drawable.tint = if inside {
+                WALL_WARNING_COLOR
+            } else {
+                wall_tint(&colors)
+            };
+            head_in_wall |= inside;
+        }
+
+        let time = clock.time();
+        let frame_ms = head_in_wall_state
+            .last_time
+            .map(|last_time| (time - last_time).max(0.0))
+            .unwrap_or(0.0);
+        head_in_wall_state.last_time = Some(time);
+        if head_in_wall {
+            events.queue.push(hit::GameplayEvent::WallContact {
+                duration_ms: frame_ms,
+                entered: !head_in_wall_state.inside,
+            });
         }
-        self.last_update_time_ms = current_time;
+        head_in_wall_state.inside = head_in_wall;
     }
 }
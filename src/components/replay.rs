@@ -0,0 +1,121 @@
+use crate::components::*;
+use crate::replay::ReplayFrame;
+use nalgebra::{Translation3, UnitQuaternion, Vector3};
+use specs::Builder;
+
+/// Poses of the song being played, saved as a replay at the end
+#[derive(Default)]
+pub struct ReplayRecording {
+    pub frames: Vec<ReplayFrame>,
+}
+
+/// Adds the poses the sabers used this frame. `simulated` is false for paused frames
+pub fn record_frame(world: &specs::World, simulated: bool) {
+    let time = world.read_resource::<clock::SongClock>().time();
+    let poses = world.read_resource::<input::TrackedPoses>();
+    world
+        .write_resource::<ReplayRecording>()
+        .frames
+        .push(ReplayFrame {
+            time,
+            simulated,
+            head: poses.head,
+            hands: [poses.hands[0].grip, poses.hands[1].grip],
+        });
+}
+
+/// Frames of the replay that is watched, empty when playing normally
+#[derive(Default)]
+pub struct ReplayPlayback {
+    pub frames: Vec<ReplayFrame>,
+    // Hash of the map the replay was recorded on, it only plays back the same on that one
+    pub map_hash: Option<String>,
+}
+
+/// Box that shows where the head of the recorded player was
+pub fn create_head(world: &mut specs::World) -> specs::Entity {
+    let mut drawable =
+        drawable::Drawable::new("cube".to_string(), "dev".to_string(), "simple".to_string());
+    drawable.enabled = false;
+    world
+        .create_entity()
+        .with(transform::Transform::new(
+            Translation3::identity(),
+            UnitQuaternion::identity(),
+            Vector3::new(0.1, 0.12, 0.12),
+        ))
+        .with(drawable)
+        .build()
+}
+
+/// Feeds the recorded poses to the sabers in place of the controllers
+pub struct ReplayPlaybackSystem {
+    head: specs::Entity,
+    next_frame: usize,
+    last_time: Option<f32>,
+}
+
+impl ReplayPlaybackSystem {
+    pub fn new(head: specs::Entity) -> Self {
+        Self {
+            head,
+            next_frame: 0,
+            last_time: None,
+        }
+    }
+}
+
+impl<'a> specs::System<'a> for ReplayPlaybackSystem {
+    type SystemData = (
+        specs::Read<'a, clock::SongClock>,
+        specs::Read<'a, ReplayPlayback>,
+        specs::Write<'a, input::TrackedPoses>,
+        specs::WriteStorage<'a, transform::Transform>,
+        specs::WriteStorage<'a, drawable::Drawable>,
+    );
+
+    fn run(
+        &mut self,
+        (clock, playback, mut poses, mut transforms, mut drawables): Self::SystemData,
+    ) {
+        let time = clock.time();
+        let frame_ms = self
+            .last_time
+            .map(|last_time| (time - last_time).max(0.0))
+            .unwrap_or(0.0);
+        self.last_time = Some(time);
+        if frame_ms > 0.0 || self.next_frame == 0 {
+            // The clock moves on before the sabers are tested against notes
+            while playback
+                .frames
+                .get(self.next_frame)
+                .map(|frame| frame.time <= time + frame_ms)
+                == Some(true)
+            {
+                self.next_frame += 1;
+            }
+        }
+        let frame = match self.next_frame.checked_sub(1) {
+            Some(i) => playback.frames[i],
+            None => return,
+        };
+        for (input, grip) in poses.hands.iter_mut().zip(frame.hands.iter()) {
+            input.grip = *grip;
+            input.aim = *grip;
+        }
+        poses.head = frame.head;
+
+        if let (Some(transform), Some(drawable)) =
+            (transforms.get_mut(self.head), drawables.get_mut(self.head))
+        {
+            match frame.head {
+                Some(head) => {
+                    transform.position = head.translation;
+                    transform.rotation = head.rotation;
+                    drawable.enabled = true;
+                }
+                None => drawable.enabled = false,
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use crate::collision::Blade;
+use crate::components::*;
+use crate::scoring::SwingTracker;
+use input::Hand;
+use nalgebra::{Point3, Translation3, UnitQuaternion, Vector3};
+use specs::{Builder, Component, Join, VecStorage};
+
+// Blade length in metres
+pub const SABER_LENGTH: f32 = 1.0;
+
+#[derive(Component)]
+#[storage(VecStorage)]
+pub struct Saber {
+    pub hand: Hand,
+    // Blade in playfield coordinates this frame and the one before
+    pub blade: Option<Blade>,
+    pub previous_blade: Option<Blade>,
+    pub swing: SwingTracker,
+}
+
+pub fn create_sabers(world: &mut specs::World) {
+    for (hand, texture) in &[(Hand::Left, "note_red"), (Hand::Right, "note_blue")] {
+        let mut drawable = drawable::Drawable::new(
+            "cube".to_string(),
+            texture.to_string(),
+            "note".to_string(),
+        );
+        drawable.enabled = false;
+        world
+            .create_entity()
+            .with(Saber {
+                hand: *hand,
+                blade: None,
+                previous_blade: None,
+                swing: Default::default(),
+            })
+            .with(transform::Transform::new(
+                Translation3::identity(),
+                UnitQuaternion::identity(),
+                Vector3::new(0.02, 0.02, SABER_LENGTH / 2.0),
+            ))
+            .with(drawable)
+            .build();
+    }
+}
+
+/// Moves the sabers to the controllers, the blade points along -z of the grip
+pub struct SaberSystem;
+
+impl<'a> specs::System<'a> for SaberSystem {
+    type SystemData = (
+        specs::Read<'a, input::TrackedPoses>,
+        specs::Read<'a, crate::colors::ColorScheme>,
+        specs::WriteStorage<'a, Saber>,
+        specs::WriteStorage<'a, transform::Transform>,
+        specs::WriteStorage<'a, drawable::Drawable>,
+    );
+
+    fn run(&mut self, (poses, colors, mut sabers, mut transforms, mut drawables): Self::SystemData) {
+        for (saber, transform, drawable) in (&mut sabers, &mut transforms, &mut drawables).join() {
+            let grip = poses.hand(saber.hand).grip;
+            saber.previous_blade = saber.blade;
+            saber.blade = grip.map(|grip| Blade {
+                base: grip * Point3::origin(),
+                tip: grip * Point3::new(0.0, 0.0, -SABER_LENGTH),
+            });
+            match saber.blade {
+                Some(blade) => {
+                    saber.swing.update(blade.tip - blade.base);
+                }
+                None => saber.swing.reset(),
+            }
+            match grip {
+                Some(grip) => {
+                    let center = grip * Point3::new(0.0, 0.0, -SABER_LENGTH / 2.0);
+                    transform.position = Translation3::new(center.x, center.y, center.z);
+                    transform.rotation = grip.rotation;
+                    drawable.enabled = true;
+                    drawable.tint = colors.hand_tint(saber.hand);
+                }
+                None => drawable.enabled = false,
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use crate::components::*;
+use crate::scoring::{CutScore, MaxScore, PendingCut};
+use hit::GameplayEvent;
+use input::Hand;
+use specs::Join;
+
+/// Score of the song that is being played
+#[derive(Default)]
+pub struct SongScore {
+    // Sum of all cuts with the combo multiplier, before modifiers
+    pub score: u32,
+    pub modifier_multiplier: f32,
+    pub cuts: Vec<CutScore>,
+    pub bad_cuts: u32,
+    pub misses: u32,
+    // Perfect score of every note judged so far
+    pub max_score: MaxScore,
+    // Good cuts that wait for the end of their swing and the multiplier they were cut with
+    pub pending: Vec<(Hand, u32, PendingCut)>,
+}
+
+impl SongScore {
+    pub fn modified_score(&self) -> u32 {
+        (self.score as f32 * self.modifier_multiplier).round() as u32
+    }
+    /// Share of the highest score possible for the notes judged so far, before modifiers
+    pub fn accuracy(&self) -> f32 {
+        match self.max_score.score {
+            0 => 0.0,
+            max_score => self.score as f32 / max_score as f32,
+        }
+    }
+    fn add_cut(&mut self, cut: CutScore, multiplier: u32) {
+        self.score += cut.total() * multiplier;
+        self.cuts.push(cut);
+    }
+}
+
+pub struct ScoreSystem;
+
+impl<'a> specs::System<'a> for ScoreSystem {
+    type SystemData = (
+        specs::Read<'a, hit::GameplayEvents>,
+        specs::Read<'a, stats::PlayerStats>,
+        specs::Write<'a, SongScore>,
+        specs::ReadStorage<'a, saber::Saber>,
+    );
+
+    fn run(&mut self, (events, stats, mut song_score, sabers): Self::SystemData) {
+        // Cuts of this frame start following the blade from the next one
+        let pending = std::mem::take(&mut song_score.pending);
+        for (hand, multiplier, mut cut) in pending {
+            let direction = (&sabers)
+                .join()
+                .find(|saber| saber.hand == hand)
+                .and_then(|saber| saber.blade)
+                .map(|blade| blade.tip - blade.base);
+            match cut.follow(direction) {
+                Some(score) => song_score.add_cut(score, multiplier),
+                None => song_score.pending.push((hand, multiplier, cut)),
+            }
+        }
+
+        for event in &events.queue {
+            match event {
+                GameplayEvent::GoodCut(cut_info) => {
+                    song_score.max_score.add(cut_info.scoring);
+                    let swing = (&sabers)
+                        .join()
+                        .find(|saber| saber.hand == cut_info.hand)
+                        .map(|saber| saber.swing)
+                        .unwrap_or_default();
+                    song_score.pending.push((
+                        cut_info.hand,
+                        stats.multiplier,
+                        PendingCut::new(cut_info.scoring, &swing, cut_info.cut.center_distance),
+                    ));
+                }
+                GameplayEvent::BadCut(cut_info) => {
+                    song_score.max_score.add(cut_info.scoring);
+                    song_score.bad_cuts += 1;
+                }
+                GameplayEvent::Miss { scoring } => {
+                    song_score.max_score.add(*scoring);
+                    song_score.misses += 1;
+                }
+                GameplayEvent::MineHit | GameplayEvent::WallContact { .. } => {}
+            }
+        }
+    }
+}
@@ -1,4 +1,8 @@
 use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::time::Instant;
 
 pub struct SoundSystem {
     device: rodio::Device,
@@ -8,48 +12,115 @@ pub struct SoundSystem {
 pub enum SoundEvent {
     // Option<String> is a name. A name is beeing used if you want to pause or continue sound, leave None if you want to play it once
     AddSound(String, Option<String>),
+    // Same as AddSound, plays faster or slower than normal
+    AddSoundWithSpeed(String, Option<String>, f32),
     PauseSound(String),
     ContinueSound(String),
+    StopSound(String),
+}
+
+#[derive(Debug)]
+pub enum AudioError {
+    Io(std::io::Error),
+    Decoder(rodio::decoder::DecoderError),
+}
+
+impl fmt::Display for AudioError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AudioError::Io(e) => write!(f, "{}", e),
+            AudioError::Decoder(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Opens an audio file, failing if it's missing or in a format we can't decode
+pub fn open_audio(path: &str) -> Result<rodio::Decoder<BufReader<File>>, AudioError> {
+    let file = File::open(path).map_err(AudioError::Io)?;
+    rodio::Decoder::new(BufReader::new(file)).map_err(AudioError::Decoder)
 }
 
 #[derive(Default)]
 pub struct SoundEvents {
     pub queue: Vec<SoundEvent>,
+    // Named sounds that began playing this frame and when exactly that happened
+    pub started: Vec<(String, Instant)>,
+    // Named sounds that played until the end
+    pub finished: Vec<String>,
 }
 
 impl<'a> specs::System<'a> for SoundSystem {
     type SystemData = (specs::Write<'a, SoundEvents>);
 
     fn run(&mut self, mut sound_events: Self::SystemData) {
+        let mut started = vec![];
         for event in &sound_events.queue {
-            match event {
-                SoundEvent::AddSound(path, name) => {
-                    let file = std::fs::File::open(path).unwrap();
-                    let sink = rodio::Sink::new(&self.device);
-                    sink.append(rodio::Decoder::new(std::io::BufReader::new(file)).unwrap());
-                    if let Some(name) = name {
-                        self.sounds.insert(name.clone(), sink);
-                    } else {
-                        sink.detach();
-                    }
+            let played = match event {
+                SoundEvent::AddSound(path, name) => (path, self.add_sound(path, name, 1.0)),
+                SoundEvent::AddSoundWithSpeed(path, name, speed) => {
+                    (path, self.add_sound(path, name, *speed))
                 }
                 SoundEvent::PauseSound(name) => {
                     if let Some(sink) = self.sounds.get(name) {
                         sink.pause();
                     }
+                    continue;
                 }
                 SoundEvent::ContinueSound(name) => {
                     if let Some(sink) = self.sounds.get(name) {
                         sink.play();
                     }
+                    continue;
+                }
+                SoundEvent::StopSound(name) => {
+                    if let Some(sink) = self.sounds.remove(name) {
+                        sink.stop();
+                    }
+                    continue;
                 }
+            };
+            match played {
+                (_, Ok(Some(name))) => started.push((name, Instant::now())),
+                (_, Ok(None)) => {}
+                (path, Err(e)) => println!("Couldn't play {}: {}", path, e),
             }
         }
         sound_events.queue.clear();
+        sound_events.started.extend(started);
+
+        let finished: Vec<String> = self
+            .sounds
+            .iter()
+            .filter(|(_, sink)| sink.empty())
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in finished {
+            self.sounds.remove(&name);
+            sound_events.finished.push(name);
+        }
     }
 }
 
 impl SoundSystem {
+    /// Starts playing a sound, returns the name if it was a named one
+    fn add_sound(
+        &mut self,
+        path: &str,
+        name: &Option<String>,
+        speed: f32,
+    ) -> Result<Option<String>, AudioError> {
+        use rodio::Source;
+        let decoder = open_audio(path)?;
+        let sink = rodio::Sink::new(&self.device);
+        sink.append(decoder.speed(speed));
+        if let Some(name) = name {
+            self.sounds.insert(name.clone(), sink);
+            Ok(Some(name.clone()))
+        } else {
+            sink.detach();
+            Ok(None)
+        }
+    }
     pub fn new() -> Self {
         let device = rodio::default_output_device().unwrap();
         Self {
@@ -0,0 +1,91 @@
+use crate::components::*;
+use std::collections::VecDeque;
+
+/// Parsed map objects that don't have entities yet, ordered by time
+#[derive(Default)]
+pub struct SpawnQueue {
+    pub notes: VecDeque<note::Note>,
+    pub obstacles: VecDeque<obstacle::Obstacle>,
+    pub arcs: VecDeque<arc::Arc>,
+}
+
+impl SpawnQueue {
+    pub fn new(
+        mut notes: Vec<note::Note>,
+        mut obstacles: Vec<obstacle::Obstacle>,
+        mut arcs: Vec<arc::Arc>,
+    ) -> Self {
+        notes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        obstacles.sort_by(|a, b| a.time.total_cmp(&b.time));
+        arcs.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Self {
+            notes: notes.into(),
+            obstacles: obstacles.into(),
+            arcs: arcs.into(),
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty() && self.obstacles.is_empty() && self.arcs.is_empty()
+    }
+}
+
+/// Creates entities for objects that are about to enter the jump window
+pub struct SpawnSystem;
+
+impl<'a> specs::System<'a> for SpawnSystem {
+    type SystemData = (
+        specs::Entities<'a>,
+        specs::Read<'a, CurrentSongInfo>,
+        specs::Read<'a, clock::SongClock>,
+        specs::Read<'a, crate::colors::ColorScheme>,
+        specs::Write<'a, SpawnQueue>,
+        specs::WriteStorage<'a, note::Note>,
+        specs::WriteStorage<'a, obstacle::Obstacle>,
+        specs::WriteStorage<'a, arc::Arc>,
+        specs::WriteStorage<'a, arc::Curve>,
+        specs::WriteStorage<'a, drawable::Drawable>,
+        specs::WriteStorage<'a, transform::Transform>,
+    );
+
+    fn run(
+        &mut self,
+        (ents, song_info, clock, colors, mut queue, mut notes, mut obstacles, mut arcs, mut curves, mut drawables, mut transforms): Self::SystemData,
+    ) {
+        let note_jump = &song_info.note_jump;
+        let spawn_time = clock.time() + note_jump.half_jump_duration * 1000.0;
+
+        while queue.notes.front().map(|note| note.time <= spawn_time) == Some(true) {
+            let note = queue.notes.pop_front().unwrap();
+            let (transform, drawable) = crate::songs::note_components(&note, note_jump, &colors);
+            ents.build_entity()
+                .with(transform, &mut transforms)
+                .with(drawable, &mut drawables)
+                .with(note, &mut notes)
+                .build();
+        }
+        while queue
+            .obstacles
+            .front()
+            .map(|obstacle| obstacle.time <= spawn_time)
+            == Some(true)
+        {
+            let obstacle = queue.obstacles.pop_front().unwrap();
+            let (transform, drawable) = crate::songs::obstacle_components(&obstacle, note_jump, &colors);
+            ents.build_entity()
+                .with(transform, &mut transforms)
+                .with(drawable, &mut drawables)
+                .with(obstacle, &mut obstacles)
+                .build();
+        }
+        while queue.arcs.front().map(|arc| arc.time <= spawn_time) == Some(true) {
+            let arc = queue.arcs.pop_front().unwrap();
+            let (transform, drawable, curve) = crate::songs::arc_components(&arc, note_jump, &colors);
+            ents.build_entity()
+                .with(transform, &mut transforms)
+                .with(drawable, &mut drawables)
+                .with(curve, &mut curves)
+                .with(arc, &mut arcs)
+                .build();
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use crate::components::*;
+use crate::scoring::MAX_MULTIPLIER;
+use hit::GameplayEvent;
+
+const START_ENERGY: f32 = 0.5;
+const GOOD_CUT_ENERGY: f32 = 0.01;
+const BAD_CUT_ENERGY: f32 = -0.1;
+const MISS_ENERGY: f32 = -0.15;
+const MINE_ENERGY: f32 = -0.15;
+// Per second with the head inside a wall
+const WALL_ENERGY: f32 = -1.3;
+// A battery has four lives
+const BATTERY_LIFE: f32 = 0.25;
+
+/// How well the player is doing in the current song
+pub struct PlayerStats {
+    pub combo: u32,
+    pub max_combo: u32,
+    pub multiplier: u32,
+    // Good cuts since the multiplier last changed, `multiplier * 2` of them raise it
+    pub multiplier_progress: u32,
+    // 0.0 to 1.0, the level fails at 0.0
+    pub energy: f32,
+    pub failed: bool,
+    no_fail: bool,
+    instafail: bool,
+    // Energy only drops in quarters and never comes back
+    battery: bool,
+}
+
+impl Default for PlayerStats {
+    fn default() -> Self {
+        Self {
+            combo: 0,
+            max_combo: 0,
+            multiplier: 1,
+            multiplier_progress: 0,
+            energy: START_ENERGY,
+            failed: false,
+            no_fail: false,
+            instafail: false,
+            battery: false,
+        }
+    }
+}
+
+impl PlayerStats {
+    pub fn new(modifiers: &crate::modifiers::Modifiers) -> Self {
+        Self {
+            energy: if modifiers.battery_energy {
+                1.0
+            } else {
+                START_ENERGY
+            },
+            no_fail: modifiers.no_fail || modifiers.zen_mode,
+            instafail: modifiers.instafail,
+            battery: modifiers.battery_energy,
+            ..Default::default()
+        }
+    }
+    pub fn good_cut(&mut self) {
+        self.combo += 1;
+        self.max_combo = self.max_combo.max(self.combo);
+        if self.multiplier < MAX_MULTIPLIER {
+            self.multiplier_progress += 1;
+            if self.multiplier_progress >= self.multiplier * 2 {
+                self.multiplier *= 2;
+                self.multiplier_progress = 0;
+            }
+        }
+        if !self.battery && !self.instafail {
+            self.add_energy(GOOD_CUT_ENERGY);
+        }
+    }
+    /// Energy lost on a mistake, battery and instafail replace the normal amount
+    pub fn penalty(&mut self, energy: f32) {
+        if self.instafail {
+            self.add_energy(-1.0);
+        } else if self.battery {
+            self.add_energy(-BATTERY_LIFE);
+        } else {
+            self.add_energy(energy);
+        }
+    }
+    /// Misses, bad cuts, mines and walls reset the combo and drop the multiplier one step
+    pub fn break_combo(&mut self) {
+        self.combo = 0;
+        self.multiplier = (self.multiplier / 2).max(1);
+        self.multiplier_progress = 0;
+    }
+    pub fn add_energy(&mut self, energy: f32) {
+        if self.failed {
+            return;
+        }
+        self.energy = (self.energy + energy).clamp(0.0, 1.0);
+        if self.energy <= 0.0 && !self.no_fail {
+            self.failed = true;
+        }
+    }
+}
+
+pub struct StatsSystem;
+
+impl<'a> specs::System<'a> for StatsSystem {
+    type SystemData = (
+        specs::Read<'a, hit::GameplayEvents>,
+        specs::Write<'a, PlayerStats>,
+    );
+
+    fn run(&mut self, (events, mut stats): Self::SystemData) {
+        for event in &events.queue {
+            match event {
+                GameplayEvent::GoodCut(_) => stats.good_cut(),
+                GameplayEvent::BadCut(_) => {
+                    stats.break_combo();
+                    stats.penalty(BAD_CUT_ENERGY);
+                }
+                GameplayEvent::Miss { .. } => {
+                    stats.break_combo();
+                    stats.penalty(MISS_ENERGY);
+                }
+                GameplayEvent::MineHit => {
+                    stats.break_combo();
+                    stats.penalty(MINE_ENERGY);
+                }
+                GameplayEvent::WallContact {
+                    duration_ms,
+                    entered,
+                } => {
+                    if *entered {
+                        stats.break_combo();
+                    }
+                    if stats.battery || stats.instafail {
+                        if *entered {
+                            stats.penalty(WALL_ENERGY);
+                        }
+                    } else {
+                        stats.add_energy(WALL_ENERGY * duration_ms / 1000.0);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collision::Cut;
+    use crate::modifiers::Modifiers;
+    use crate::scoring::ScoringType;
+    use hit::CutInfo;
+    use input::Hand;
+    use nalgebra::Vector3;
+    use specs::RunNow;
+
+    fn good_cut() -> GameplayEvent {
+        GameplayEvent::GoodCut(CutInfo {
+            hand: Hand::Left,
+            scoring: ScoringType::Normal,
+            wrong_color: false,
+            wrong_direction: false,
+            cut: Cut {
+                direction: Vector3::new(0.0, 1.0, 0.0),
+                center_distance: 0.0,
+            },
+        })
+    }
+
+    fn miss() -> GameplayEvent {
+        GameplayEvent::Miss {
+            scoring: ScoringType::Normal,
+        }
+    }
+
+    // Judges `events` in one frame
+    fn judge(stats: PlayerStats, events: Vec<GameplayEvent>) -> PlayerStats {
+        let mut world = specs::World::new();
+        register_default(&mut world);
+        world.add_resource(stats);
+        world.write_resource::<hit::GameplayEvents>().queue = events;
+        StatsSystem.run_now(&world.res);
+        let stats = std::mem::take(&mut *world.write_resource::<PlayerStats>());
+        stats
+    }
+
+    fn assert_energy(stats: &PlayerStats, energy: f32) {
+        assert!(
+            (stats.energy - energy).abs() < 1e-4,
+            "energy {} instead of {}",
+            stats.energy,
+            energy
+        );
+    }
+
+    #[test]
+    fn multiplier_doubles_up_to_eight() {
+        let mut stats = PlayerStats::default();
+        let mut multipliers = vec![];
+        for _ in 0..16 {
+            stats.good_cut();
+            multipliers.push(stats.multiplier);
+        }
+        // 2 cuts to reach x2, 4 more to x4 and 8 more to x8
+        assert_eq!(
+            multipliers,
+            [1, 2, 2, 2, 2, 4, 4, 4, 4, 4, 4, 4, 4, 8, 8, 8]
+        );
+        assert_eq!(stats.combo, 16);
+    }
+
+    #[test]
+    fn mistakes_drop_the_multiplier_one_step() {
+        let mut stats = judge(PlayerStats::default(), vec![good_cut(); 14]);
+        assert_eq!(stats.multiplier, 8);
+        stats = judge(stats, vec![GameplayEvent::MineHit]);
+        assert_eq!((stats.multiplier, stats.combo, stats.max_combo), (4, 0, 14));
+        stats = judge(stats, vec![miss()]);
+        assert_eq!(stats.multiplier, 2);
+        stats = judge(stats, vec![miss(); 2]);
+        assert_eq!(stats.multiplier, 1);
+    }
+
+    #[test]
+    fn energy_rises_with_good_cuts_and_drops_with_mistakes() {
+        let mut stats = judge(PlayerStats::default(), vec![good_cut(); 10]);
+        assert_energy(&stats, 0.6);
+        stats = judge(stats, vec![miss(), GameplayEvent::MineHit]);
+        assert_energy(&stats, 0.3);
+        // Walls drain energy for as long as the head is inside
+        stats = judge(
+            stats,
+            vec![GameplayEvent::WallContact {
+                duration_ms: 100.0,
+                entered: true,
+            }],
+        );
+        assert_energy(&stats, 0.17);
+        assert!(!stats.failed);
+        stats = judge(stats, vec![good_cut(); 100]);
+        assert_energy(&stats, 1.0);
+    }
+
+    #[test]
+    fn running_out_of_energy_fails_unless_no_fail() {
+        let misses = vec![miss(); 4];
+        let stats = judge(PlayerStats::default(), misses.clone());
+        assert!(stats.failed);
+        assert_energy(&stats, 0.0);
+        // Energy doesn't come back after failing
+        assert_energy(&judge(stats, vec![good_cut()]), 0.0);
+
+        let modifiers = Modifiers {
+            no_fail: true,
+            ..Default::default()
+        };
+        let stats = judge(PlayerStats::new(&modifiers), misses);
+        assert!(!stats.failed);
+        assert_energy(&judge(stats, vec![good_cut()]), 0.01);
+    }
+
+    #[test]
+    fn battery_loses_a_quarter_per_mistake() {
+        let modifiers = Modifiers {
+            battery_energy: true,
+            ..Default::default()
+        };
+        let mut stats = judge(PlayerStats::new(&modifiers), vec![good_cut(); 5]);
+        assert_energy(&stats, 1.0);
+        stats = judge(stats, vec![GameplayEvent::MineHit; 3]);
+        assert_energy(&stats, 0.25);
+        assert!(!stats.failed);
+        stats = judge(
+            stats,
+            vec![GameplayEvent::WallContact {
+                duration_ms: 10.0,
+                entered: true,
+            }],
+        );
+        assert!(stats.failed);
+    }
+
+    #[test]
+    fn instafail_fails_on_the_first_mistake() {
+        let modifiers = Modifiers {
+            instafail: true,
+            ..Default::default()
+        };
+        let stats = judge(
+            PlayerStats::new(&modifiers),
+            vec![good_cut(), GameplayEvent::MineHit],
+        );
+        assert!(stats.failed);
+    }
+}
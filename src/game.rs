@@ -0,0 +1,447 @@
+use crate::components::*;
+use specs::{Dispatcher, DispatcherBuilder, World};
+use std::time::{Duration, Instant};
+
+// Time between loading a song and the start of its audio
+const COUNTDOWN_MS: f32 = 3000.0;
+const RESULTS_DURATION: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameState {
+    Menu,
+    Loading,
+    Countdown,
+    Playing,
+    Paused,
+    Results,
+    Exiting,
+}
+
+#[derive(Clone, Debug)]
+pub struct SongSelection {
+    pub name: String,
+    pub characteristic: String,
+    pub difficulty: String,
+    pub modifiers: crate::modifiers::Modifiers,
+}
+
+pub enum StateEvent {
+    SelectSong(SongSelection),
+}
+
+/// Requests to change the game state, handled once per frame before the systems run
+#[derive(Default)]
+pub struct StateEvents {
+    pub queue: Vec<StateEvent>,
+}
+
+/// Session state of the headset, written by the window every frame
+#[derive(Default)]
+pub struct XrStatus {
+    pub focused: bool,
+    pub exiting: bool,
+}
+
+pub struct Game<'a, 'b> {
+    pub state: GameState,
+    entered_at: Instant,
+    selection: Option<SongSelection>,
+    // Pauses caused by the headset losing focus end when the focus comes back
+    resume_on_focus: bool,
+    gameplay: Dispatcher<'a, 'b>,
+}
+
+impl<'a, 'b> Game<'a, 'b> {
+    pub fn new(world: &mut World) -> Self {
+        world.add_resource(StateEvents {
+            ..Default::default()
+        });
+        world.add_resource(XrStatus {
+            ..Default::default()
+        });
+        world.add_resource(crate::render::SpectatorCamera {
+            ..Default::default()
+        });
+        Self {
+            state: GameState::Menu,
+            entered_at: Instant::now(),
+            selection: None,
+            resume_on_focus: false,
+            gameplay: gameplay_dispatcher(),
+        }
+    }
+
+    /// Runs the systems of the current state and records the frame for the replay.
+    /// Sound and rendering run in every state
+    pub fn dispatch(&mut self, world: &World) {
+        match self.state {
+            GameState::Countdown | GameState::Playing => {
+                self.gameplay.dispatch(&world.res);
+                replay::record_frame(world, true);
+            }
+            // The sabers keep moving, a replay has to follow them to score the same
+            GameState::Paused => replay::record_frame(world, false),
+            _ => {}
+        }
+    }
+
+    pub fn update(&mut self, world: &mut World) {
+        let events: Vec<StateEvent> = world
+            .write_resource::<StateEvents>()
+            .queue
+            .drain(..)
+            .collect();
+        let finished: Vec<String> = world
+            .write_resource::<sound::SoundEvents>()
+            .finished
+            .drain(..)
+            .collect();
+        let (focused, exiting) = {
+            let xr_status = world.read_resource::<XrStatus>();
+            (xr_status.focused, xr_status.exiting)
+        };
+
+        let menu_pressed = world.read_resource::<input::TrackedPoses>().menu_pressed();
+        for event in events {
+            match event {
+                StateEvent::SelectSong(selection) => {
+                    if self.state == GameState::Menu {
+                        self.selection = Some(selection);
+                        self.enter(GameState::Loading, world);
+                    }
+                }
+            }
+        }
+        if exiting {
+            self.enter(GameState::Exiting, world);
+        }
+
+        match self.state {
+            GameState::Loading => {
+                let selection = self.selection.clone().unwrap();
+                *world.write_resource::<crate::modifiers::Modifiers>() = selection.modifiers;
+                match crate::songs::load_song(
+                    selection.name,
+                    selection.characteristic,
+                    selection.difficulty,
+                    world,
+                ) {
+                    Err(e) => {
+                        println!("Error while loading song: {}", e);
+                        self.enter(GameState::Menu, world);
+                    }
+                    Ok(report) => {
+                        if !report.is_clean() {
+                            println!("Song loaded with {} warnings:", report.warnings.len());
+                        }
+                        for warning in &report.warnings {
+                            println!("  {}", warning);
+                        }
+                        if !replay_matches_map(world) {
+                            println!("Invalid replay: it was recorded on a different version of the map");
+                            self.enter(GameState::Menu, world);
+                            return;
+                        }
+                        // The audio only starts after the countdown, so it's checked here
+                        let song_file = world.read_resource::<CurrentSongInfo>().song_file.clone();
+                        match sound::open_audio(&song_file) {
+                            Err(e) => {
+                                println!("Error while loading song audio {}: {}", song_file, e);
+                                self.enter(GameState::Menu, world);
+                            }
+                            Ok(_) => {
+                                print_personal_best(world);
+                                self.enter(GameState::Countdown, world);
+                            }
+                        }
+                    }
+                }
+            }
+            GameState::Countdown => {
+                if world.read_resource::<clock::SongClock>().time() >= 0.0 {
+                    crate::songs::play_song_audio(world);
+                    self.enter(GameState::Playing, world);
+                }
+            }
+            GameState::Playing => {
+                let failed = world.read_resource::<stats::PlayerStats>().failed;
+                if failed {
+                    send_sound(
+                        world,
+                        sound::SoundEvent::PauseSound(clock::SONG_SOUND.to_string()),
+                    );
+                    self.enter(GameState::Results, world);
+                } else if finished.iter().any(|name| name == clock::SONG_SOUND) {
+                    self.enter(GameState::Results, world);
+                } else if !focused || menu_pressed {
+                    self.resume_on_focus = !focused;
+                    self.enter(GameState::Paused, world);
+                }
+            }
+            GameState::Paused => {
+                if menu_pressed || (self.resume_on_focus && focused) {
+                    self.enter(GameState::Playing, world);
+                }
+            }
+            GameState::Results => {
+                if self.entered_at.elapsed() > RESULTS_DURATION {
+                    // A watched replay can't be started again, there's nothing left to show
+                    if is_replay(world) {
+                        self.enter(GameState::Exiting, world);
+                    } else {
+                        self.enter(GameState::Menu, world);
+                    }
+                }
+            }
+            GameState::Menu => {
+                // Without a song list the menu offers the last song again
+                if menu_pressed && self.selection.is_some() {
+                    self.enter(GameState::Loading, world);
+                }
+            }
+            GameState::Exiting => {}
+        }
+    }
+
+    fn enter(&mut self, state: GameState, world: &mut World) {
+        if state == self.state {
+            return;
+        }
+        println!("{:?} -> {:?}", self.state, state);
+        let now = Instant::now();
+        match (self.state, state) {
+            (GameState::Playing, GameState::Paused) => {
+                world.write_resource::<clock::SongClock>().pause(now);
+                let seconds = world.read_resource::<clock::SongClock>().time() / 1000.0;
+                println!(
+                    "Paused at beat {:.1}",
+                    world
+                        .read_resource::<CurrentSongInfo>()
+                        .tempo_map
+                        .seconds_to_beat(seconds)
+                );
+                send_sound(
+                    world,
+                    sound::SoundEvent::PauseSound(clock::SONG_SOUND.to_string()),
+                );
+            }
+            (GameState::Paused, GameState::Playing) => {
+                world.write_resource::<clock::SongClock>().resume(now);
+                send_sound(
+                    world,
+                    sound::SoundEvent::ContinueSound(clock::SONG_SOUND.to_string()),
+                );
+            }
+            (_, GameState::Countdown) => {
+                world
+                    .write_resource::<clock::SongClock>()
+                    .start_countdown(now, COUNTDOWN_MS);
+            }
+            (_, GameState::Results) => {
+                if let Some(selection) = &self.selection {
+                    print_results(world, selection);
+                    if is_player_run(world) {
+                        let replay = save_replay(world, selection);
+                        save_score(world, selection, replay);
+                    }
+                }
+                crate::songs::unload_song(world);
+            }
+            (GameState::Results, GameState::Menu) => {
+                if let Some(selection) = &self.selection {
+                    print_personal_best(world);
+                    println!(
+                        "Press the menu button to play {} again or quit from the headset",
+                        selection.name
+                    );
+                }
+            }
+            (_, GameState::Menu) | (_, GameState::Exiting) => {
+                crate::songs::unload_song(world);
+            }
+            _ => {}
+        }
+        self.state = state;
+        self.entered_at = now;
+    }
+}
+
+/// Systems that run while a song is played
+pub fn gameplay_dispatcher<'a, 'b>() -> Dispatcher<'a, 'b> {
+    DispatcherBuilder::new()
+        .with(clock::SongClockSystem, "Song Clock System", &[])
+        .with(spawn::SpawnSystem, "Spawn System", &["Song Clock System"])
+        .with(
+            lane::LaneSystem::default(),
+            "Lane System",
+            &["Song Clock System"],
+        )
+        .with(
+            lights::EnvironmentLightsSystem::default(),
+            "Environment Lights System",
+            &["Lane System"],
+        )
+        .with(note::NoteSystem, "Note System", &["Spawn System"])
+        .with(arc::ArcSystem, "Arc System", &["Spawn System"])
+        .with(hit::HitSystem, "Hit System", &["Note System"])
+        .with(obstacle::ObstacleSystem, "Obstacle System", &["Spawn System"])
+        .with(
+            stats::StatsSystem,
+            "Stats System",
+            &["Hit System", "Obstacle System"],
+        )
+        .with(score::ScoreSystem, "Score System", &["Stats System"])
+        .build()
+}
+
+/// The renderer has no text yet, so results, personal bests and history go to stdout
+pub fn print_results(world: &World, selection: &SongSelection) {
+    let song_score = world.read_resource::<score::SongScore>();
+    let stats = world.read_resource::<stats::PlayerStats>();
+    let result = if stats.failed { "Failed" } else { "Finished" };
+    println!("{} {} ({})", result, selection.name, selection.difficulty);
+    println!(
+        "Score: {} (x{:.2} from modifiers), max combo {}, {} good cuts, {} bad cuts, {} misses",
+        song_score.modified_score(),
+        song_score.modifier_multiplier,
+        stats.max_combo,
+        song_score.cuts.len(),
+        song_score.bad_cuts,
+        song_score.misses
+    );
+}
+
+/// Runs of the bot and watched replays don't count as the player's
+fn is_player_run(world: &World) -> bool {
+    !world.read_resource::<PlayerSettings>().autoplay && !is_replay(world)
+}
+
+fn is_replay(world: &World) -> bool {
+    !world
+        .read_resource::<replay::ReplayPlayback>()
+        .frames
+        .is_empty()
+}
+
+/// A watched replay only plays back the same on the map it was recorded on
+fn replay_matches_map(world: &World) -> bool {
+    match &world.read_resource::<replay::ReplayPlayback>().map_hash {
+        Some(map_hash) => *map_hash == world.read_resource::<CurrentSongInfo>().map_hash,
+        None => true,
+    }
+}
+
+/// Writes the recorded run to `./replays` and returns its path
+fn save_replay(world: &World, selection: &SongSelection) -> Option<String> {
+    let song_info = world.read_resource::<CurrentSongInfo>();
+    let replay = crate::replay::Replay {
+        song: song_info.folder.clone(),
+        characteristic: song_info.characteristic.clone(),
+        difficulty: song_info.difficulty.clone(),
+        map_hash: song_info.map_hash.clone(),
+        modifiers: selection.modifiers.to_list(),
+        note_jump_speed: song_info.note_jump.speed,
+        score: world.read_resource::<score::SongScore>().modified_score(),
+        frames: std::mem::take(&mut world.write_resource::<replay::ReplayRecording>().frames),
+    };
+    let dir = std::path::Path::new("./replays");
+    // Named after the folder, the selection may be a title with characters paths can't hold
+    let path = dir.join(format!(
+        "{} {} {} {}.replay",
+        replay.song,
+        replay.characteristic,
+        replay.difficulty,
+        crate::highscores::now()
+    ));
+    match std::fs::create_dir_all(dir).and_then(|_| replay.save(&path)) {
+        Ok(()) => {
+            println!("Replay saved to {}", path.display());
+            Some(path.to_string_lossy().into_owned())
+        }
+        Err(e) => {
+            println!("Couldn't save the replay: {}", e);
+            None
+        }
+    }
+}
+
+/// Adds the run to the score database and tells if it beat the personal best
+fn save_score(world: &World, selection: &SongSelection, replay: Option<String>) {
+    let song_info = world.read_resource::<CurrentSongInfo>();
+    if song_info.map_hash.is_empty() {
+        return;
+    }
+    let mut database = match crate::highscores::ScoreDatabase::load() {
+        Ok(database) => database,
+        Err(e) => {
+            println!("Couldn't read the scores, this run isn't saved: {}", e);
+            return;
+        }
+    };
+    let song_score = world.read_resource::<score::SongScore>();
+    let stats = world.read_resource::<stats::PlayerStats>();
+    let entry = crate::highscores::ScoreEntry {
+        map_hash: song_info.map_hash.clone(),
+        characteristic: song_info.characteristic.clone(),
+        difficulty: song_info.difficulty.clone(),
+        song: song_info.folder.clone(),
+        score: song_score.modified_score(),
+        accuracy: song_score.accuracy(),
+        max_combo: stats.max_combo,
+        misses: song_score.misses,
+        failed: stats.failed,
+        modifiers: selection.modifiers.to_list(),
+        date: crate::highscores::now(),
+        replay,
+    };
+    let best = database
+        .personal_best(&entry.map_hash, &entry.characteristic, &entry.difficulty)
+        .map(|best| best.score);
+    match best {
+        _ if entry.failed => {}
+        Some(best) if best >= entry.score => println!("Personal best: {}", best),
+        Some(best) => println!("New personal best! {} more than {}", entry.score - best, best),
+        None => println!("New personal best!"),
+    }
+    database.entries.push(entry);
+    if let Err(e) = database.save() {
+        println!("Couldn't save the score: {}", e);
+    }
+}
+
+/// Shows the best and the last runs of the song that was loaded last
+fn print_personal_best(world: &World) {
+    // Number of past runs shown besides the best one
+    const HISTORY_LENGTH: usize = 5;
+    let song_info = world.read_resource::<CurrentSongInfo>();
+    let database = match crate::highscores::ScoreDatabase::load() {
+        Ok(database) => database,
+        Err(e) => {
+            println!("Couldn't read the scores: {}", e);
+            return;
+        }
+    };
+    let (map_hash, characteristic, difficulty) = (
+        &song_info.map_hash,
+        &song_info.characteristic,
+        &song_info.difficulty,
+    );
+    match database.personal_best(map_hash, characteristic, difficulty) {
+        Some(best) => println!("Personal best: {}", crate::highscores::describe(best)),
+        None => println!("No personal best yet"),
+    }
+    for entry in database
+        .history(map_hash, characteristic, difficulty)
+        .iter()
+        .take(HISTORY_LENGTH)
+    {
+        println!("  {}", crate::highscores::describe(entry));
+    }
+}
+
+fn send_sound(world: &mut World, event: sound::SoundEvent) {
+    world
+        .write_resource::<sound::SoundEvents>()
+        .queue
+        .push(event);
+}
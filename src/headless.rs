@@ -0,0 +1,153 @@
+use crate::components::*;
+use crate::game::SongSelection;
+use crate::replay::Replay;
+use specs::{Join, RunNow};
+use std::path::PathBuf;
+
+const FRAME_MS: f32 = 1000.0 / 90.0;
+const COUNTDOWN_MS: f32 = 2000.0;
+// Cuts keep following the swing for a while after the last object is gone
+const END_MS: f32 = 1000.0;
+
+fn load(
+    selection: &SongSelection,
+    note_jump_speed: Option<f32>,
+    autoplay: bool,
+    song_dirs: &[PathBuf],
+) -> Option<specs::World> {
+    let mut world = specs::World::new();
+    register_default(&mut world);
+    {
+        let mut settings = world.write_resource::<PlayerSettings>();
+        settings.note_jump_speed = note_jump_speed;
+        settings.autoplay = autoplay;
+        settings.song_dirs = song_dirs.to_vec();
+    }
+    world.add_resource(clock::SongClock::manual());
+    *world.write_resource::<crate::modifiers::Modifiers>() = selection.modifiers;
+    match crate::songs::load_song(
+        selection.name.clone(),
+        selection.characteristic.clone(),
+        selection.difficulty.clone(),
+        &mut world,
+    ) {
+        Err(e) => {
+            println!("Error while loading song: {}", e);
+            return None;
+        }
+        Ok(report) => {
+            for warning in &report.warnings {
+                println!("  {}", warning);
+            }
+        }
+    }
+    saber::create_sabers(&mut world);
+    Some(world)
+}
+
+fn finish_frame(world: &mut specs::World) {
+    maintain(world);
+    // Nothing plays sounds or rumbles here
+    world.write_resource::<sound::SoundEvents>().queue.clear();
+    world.write_resource::<input::HapticEvents>().queue.clear();
+}
+
+/// Plays a song with the autoplay bot as fast as possible, without headset, window and audio
+pub fn play(selection: SongSelection, note_jump_speed: Option<f32>, song_dirs: &[PathBuf]) {
+    let mut world = match load(&selection, note_jump_speed, true, song_dirs) {
+        Some(world) => world,
+        None => return,
+    };
+    world
+        .write_resource::<clock::SongClock>()
+        .set_time(-COUNTDOWN_MS);
+
+    let mut autoplay = autoplay::AutoplaySystem::default();
+    let mut sabers = saber::SaberSystem;
+    let mut gameplay = crate::game::gameplay_dispatcher();
+    let mut end_time = None;
+    loop {
+        // Same order as the game loop, the sabers see the clock of the previous frame
+        autoplay.run_now(&world.res);
+        sabers.run_now(&world.res);
+        let speed = world.read_resource::<clock::SongClock>().speed;
+        world
+            .write_resource::<clock::SongClock>()
+            .advance(FRAME_MS * speed);
+        gameplay.dispatch(&world.res);
+        finish_frame(&mut world);
+
+        if world.read_resource::<stats::PlayerStats>().failed {
+            break;
+        }
+        let time = world.read_resource::<clock::SongClock>().time();
+        let objects = (&world.read_storage::<note::Note>()).join().count()
+            + (&world.read_storage::<obstacle::Obstacle>()).join().count();
+        if objects == 0
+            && world.read_resource::<spawn::SpawnQueue>().is_empty()
+            && time > *end_time.get_or_insert(time + END_MS * speed)
+        {
+            break;
+        }
+    }
+    crate::game::print_results(&world, &selection);
+}
+
+/// Feeds the poses of a replay through the game again. The replay is valid if they give the recorded score
+pub fn rescore(replay: &Replay, song_dirs: &[PathBuf]) -> bool {
+    let modifiers = match crate::modifiers::Modifiers::from_list(&replay.modifiers) {
+        Ok(modifiers) => modifiers,
+        Err(e) => {
+            println!("Invalid replay: {}", e);
+            return false;
+        }
+    };
+    let selection = SongSelection {
+        name: replay.song.clone(),
+        characteristic: replay.characteristic.clone(),
+        difficulty: replay.difficulty.clone(),
+        modifiers,
+    };
+    let mut world = match load(&selection, Some(replay.note_jump_speed), false, song_dirs) {
+        Some(world) => world,
+        None => return false,
+    };
+    if world.read_resource::<CurrentSongInfo>().map_hash != replay.map_hash {
+        println!("Invalid replay: it was recorded on a different version of the map");
+        return false;
+    }
+
+    let mut sabers = saber::SaberSystem;
+    let mut gameplay = crate::game::gameplay_dispatcher();
+    for frame in &replay.frames {
+        {
+            let mut poses = world.write_resource::<input::TrackedPoses>();
+            poses.head = frame.head;
+            for (input, grip) in poses.hands.iter_mut().zip(frame.hands.iter()) {
+                input.grip = *grip;
+                input.aim = *grip;
+            }
+        }
+        sabers.run_now(&world.res);
+        if frame.simulated {
+            world
+                .write_resource::<clock::SongClock>()
+                .set_time(frame.time);
+            gameplay.dispatch(&world.res);
+        }
+        finish_frame(&mut world);
+    }
+    crate::game::print_results(&world, &selection);
+
+    let score = world.read_resource::<score::SongScore>().modified_score();
+    if score == replay.score {
+        println!("Replay is valid");
+        true
+    } else {
+        println!(
+            "Invalid replay: recorded score {}, the inputs score {}",
+            replay.score, score
+        );
+        false
+    }
+}
@@ -0,0 +1,238 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const FILE_NAME: &str = "scores.json";
+
+/// One finished or failed run of a map
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScoreEntry {
+    pub map_hash: String,
+    pub characteristic: String,
+    pub difficulty: String,
+    // Folder the song was loaded from when it was played, only for showing
+    pub song: String,
+    pub score: u32,
+    // 0.0 to 1.0 of the highest score possible, before modifiers
+    pub accuracy: f32,
+    pub max_combo: u32,
+    pub misses: u32,
+    pub failed: bool,
+    // Short modifier codes, as given to `Modifiers::from_list`
+    pub modifiers: String,
+    // Seconds since the unix epoch
+    pub date: u64,
+    pub replay: Option<String>,
+}
+
+impl ScoreEntry {
+    /// Names match like `SongInfo::find_beatmap` does, older entries kept them as typed
+    pub fn is_map(&self, map_hash: &str, characteristic: &str, difficulty: &str) -> bool {
+        self.map_hash == map_hash
+            && self.characteristic.eq_ignore_ascii_case(characteristic)
+            && self.difficulty.eq_ignore_ascii_case(difficulty)
+    }
+}
+
+/// Every run played on this machine, stored as json in the user's data directory
+#[derive(Default, Serialize, Deserialize)]
+pub struct ScoreDatabase {
+    pub entries: Vec<ScoreEntry>,
+}
+
+impl ScoreDatabase {
+    /// A missing file is an empty database
+    pub fn load() -> Result<Self, String> {
+        let path = database_path();
+        match std::fs::File::open(&path) {
+            Ok(file) => serde_json::from_reader(std::io::BufReader::new(file))
+                .map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = database_path();
+        let write = || -> std::io::Result<()> {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            // Written next to the old file first, so a crash can't lose every score
+            let temp_path = path.with_extension("json.tmp");
+            let file = std::fs::File::create(&temp_path)?;
+            serde_json::to_writer_pretty(std::io::BufWriter::new(file), self)?;
+            std::fs::rename(&temp_path, &path)
+        };
+        write().map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Runs of one difficulty, newest first
+    pub fn history(
+        &self,
+        map_hash: &str,
+        characteristic: &str,
+        difficulty: &str,
+    ) -> Vec<&ScoreEntry> {
+        let mut entries: Vec<&ScoreEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.is_map(map_hash, characteristic, difficulty))
+            .collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.date));
+        entries
+    }
+
+    /// Highest score of a run that wasn't failed
+    pub fn personal_best(
+        &self,
+        map_hash: &str,
+        characteristic: &str,
+        difficulty: &str,
+    ) -> Option<&ScoreEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.is_map(map_hash, characteristic, difficulty) && !entry.failed)
+            .max_by_key(|entry| entry.score)
+    }
+}
+
+/// `$XDG_DATA_HOME/slashmania`, falling back to `~/.local/share/slashmania`,
+/// `%APPDATA%\slashmania` and the working directory
+pub fn data_dir() -> PathBuf {
+    let var = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+    if let Some(dir) = var("XDG_DATA_HOME") {
+        PathBuf::from(dir).join("slashmania")
+    } else if let Some(dir) = var("APPDATA") {
+        PathBuf::from(dir).join("slashmania")
+    } else if let Some(home) = var("HOME") {
+        PathBuf::from(home).join(".local/share/slashmania")
+    } else {
+        PathBuf::from(".")
+    }
+}
+
+fn database_path() -> PathBuf {
+    data_dir().join(FILE_NAME)
+}
+
+pub fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0)
+}
+
+/// `YYYY-MM-DD HH:MM` in UTC
+pub fn format_date(date: u64) -> String {
+    let days = (date / 86400) as i64;
+    let seconds = date % 86400;
+    // Civil date from days since the epoch, counted in 400 year eras starting in March
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60
+    )
+}
+
+/// One line summary of a run
+pub fn describe(entry: &ScoreEntry) -> String {
+    let modifiers = if entry.modifiers.is_empty() {
+        String::new()
+    } else {
+        format!(" [{}]", entry.modifiers)
+    };
+    format!(
+        "{} {:>8} {:6.2}% combo {} misses {}{}{}",
+        format_date(entry.date),
+        entry.score,
+        entry.accuracy * 100.0,
+        entry.max_combo,
+        entry.misses,
+        modifiers,
+        if entry.failed { " (failed)" } else { "" }
+    )
+}
+
+/// Best run of every difficulty played, or all runs of one song folder
+pub fn print_scores(song: Option<&str>) {
+    let database = match ScoreDatabase::load() {
+        Ok(database) => database,
+        Err(e) => {
+            println!("Couldn't read the scores: {}", e);
+            return;
+        }
+    };
+    let mut maps: Vec<(&str, &str, &str, &str)> = vec![];
+    for entry in &database.entries {
+        let map = (
+            entry.song.as_str(),
+            entry.map_hash.as_str(),
+            entry.characteristic.as_str(),
+            entry.difficulty.as_str(),
+        );
+        if song.map(|song| song == entry.song) != Some(false) && !maps.contains(&map) {
+            maps.push(map);
+        }
+    }
+    maps.sort();
+    if maps.is_empty() {
+        println!("No scores yet");
+    }
+    for (song_name, map_hash, characteristic, difficulty) in maps {
+        println!("{} {} {}", song_name, characteristic, difficulty);
+        match song {
+            Some(_) => {
+                for entry in database.history(map_hash, characteristic, difficulty) {
+                    println!("  {}", describe(entry));
+                }
+            }
+            None => match database.personal_best(map_hash, characteristic, difficulty) {
+                Some(best) => println!("  {}", describe(best)),
+                None => println!("  Not passed yet"),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_match_whatever_the_spelling_of_the_difficulty() {
+        let entry = ScoreEntry {
+            map_hash: "1be67db8685a3e4991f229a83b59f3e17dd06aba".to_string(),
+            characteristic: "Standard".to_string(),
+            difficulty: "ExpertPlus".to_string(),
+            song: "Lopsided".to_string(),
+            score: 1000,
+            accuracy: 0.9,
+            max_combo: 10,
+            misses: 0,
+            failed: false,
+            modifiers: String::new(),
+            date: 0,
+            replay: None,
+        };
+        assert!(entry.is_map(&entry.map_hash, "standard", "expertplus"));
+        assert!(!entry.is_map(&entry.map_hash, "Standard", "Expert"));
+        assert!(!entry.is_map("0000", "Standard", "ExpertPlus"));
+    }
+}
@@ -0,0 +1,75 @@
+use crate::components::{arc::Arc, note::Note, obstacle::Obstacle};
+use nalgebra::{UnitQuaternion, Vector3};
+
+// Events this close to an object count as being at the same time
+const SAME_TIME_MS: f32 = 0.5;
+
+/// Turns the lane objects spawn in, only used by 360 and 90 degree maps
+#[derive(Clone, Copy, Debug)]
+pub struct RotationEvent {
+    // Song time in ms
+    pub time: f32,
+    // Early rotations also turn the objects at their own time, late ones only later objects
+    pub early: bool,
+    // Clockwise seen from above
+    pub degrees: f32,
+}
+
+/// Degrees of the v2 rotation event values
+pub fn rotation_from_raw(value: i64) -> Option<f32> {
+    match value {
+        0..=3 => Some((value - 4) as f32 * 15.0),
+        4..=7 => Some((value - 3) as f32 * 15.0),
+        _ => None,
+    }
+}
+
+/// Turn of a lane around the player, the lane of 0 degrees comes straight down +z
+pub fn lane_rotation(degrees: f32) -> UnitQuaternion<f32> {
+    // Clockwise from above turns +z towards -x, the right of the player
+    UnitQuaternion::from_axis_angle(&Vector3::y_axis(), -degrees.to_radians())
+}
+
+/// Rotation of the lane over the song
+#[derive(Clone, Debug, Default)]
+pub struct LaneRotations {
+    events: Vec<RotationEvent>,
+}
+
+impl LaneRotations {
+    pub fn new(mut events: Vec<RotationEvent>) -> Self {
+        events.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Self { events }
+    }
+
+    /// Lane rotation of an object at `time`
+    pub fn rotation_at(&self, time: f32) -> f32 {
+        self.events
+            .iter()
+            .take_while(|event| event.time <= time + SAME_TIME_MS)
+            .filter(|event| event.early || event.time < time - SAME_TIME_MS)
+            .map(|event| event.degrees)
+            .sum()
+    }
+
+    pub fn apply(&self, notes: &mut [Note], obstacles: &mut [Obstacle], arcs: &mut [Arc]) {
+        if self.events.is_empty() {
+            return;
+        }
+        for note in notes.iter_mut() {
+            note.rotation = self.rotation_at(note.time);
+        }
+        for obstacle in obstacles.iter_mut() {
+            obstacle.rotation = self.rotation_at(obstacle.time);
+        }
+        for arc in arcs.iter_mut() {
+            arc.rotation = self.rotation_at(arc.time);
+        }
+    }
+
+    pub fn mirror(&mut self) {
+        for event in self.events.iter_mut() {
+            event.degrees = -event.degrees;
+        }
+    }
+}
@@ -0,0 +1,400 @@
+use crate::parser::{self, ParseError, ParseErrorKind};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_SONG_DIR: &str = "./assets/songs";
+const CACHE_FILE: &str = "library.json";
+// Bumped when the cached fields change, older caches are scanned again
+const CACHE_VERSION: u32 = 2;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LibraryDifficulties {
+    pub characteristic: String,
+    pub difficulties: Vec<String>,
+}
+
+/// What the song selection needs to know about a song without parsing its maps
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LibrarySong {
+    pub dir: PathBuf,
+    pub title: String,
+    pub sub_title: String,
+    pub artist: String,
+    pub mapper: String,
+    pub bpm: f32,
+    // Length of the audio in seconds, if it could be read
+    pub duration: Option<f32>,
+    // Where the song selection starts playing the audio, in seconds
+    pub preview_start_time: f32,
+    pub difficulties: Vec<LibraryDifficulties>,
+    pub cover: Option<PathBuf>,
+    pub hash: String,
+    // Newest modification time of the files in the song folder, in ms since the epoch
+    pub modified: u64,
+}
+
+/// Why a song couldn't be picked from the library
+#[derive(Debug)]
+pub enum LibraryError {
+    // No song has this folder name, map hash or title
+    UnknownSong(String),
+    // The song was found but its files can't be read or lack the difficulty
+    Parse(ParseError),
+}
+
+impl fmt::Display for LibraryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LibraryError::UnknownSong(name) => {
+                write!(f, "no song has the folder name, map hash or title {}", name)
+            }
+            LibraryError::Parse(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for LibraryError {}
+
+impl From<ParseError> for LibraryError {
+    fn from(e: ParseError) -> Self {
+        LibraryError::Parse(e)
+    }
+}
+
+/// A song folder that couldn't be read, kept so it's only scanned again once it changes
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BrokenSong {
+    pub dir: PathBuf,
+    pub error: String,
+    pub modified: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Library {
+    #[serde(default)]
+    version: u32,
+    pub songs: Vec<LibrarySong>,
+    pub broken: Vec<BrokenSong>,
+}
+
+impl Library {
+    /// Scans `dirs` with the help of the index cached in the data directory, then updates the cache
+    pub fn load(dirs: &[PathBuf]) -> Self {
+        let cache_path = crate::highscores::data_dir().join(CACHE_FILE);
+        let cache = std::fs::File::open(&cache_path)
+            .ok()
+            .and_then(|file| serde_json::from_reader(std::io::BufReader::new(file)).ok())
+            .filter(|cache: &Library| cache.version == CACHE_VERSION)
+            .unwrap_or_default();
+        let library = Self::scan(dirs, &cache);
+        let write = || -> std::io::Result<()> {
+            std::fs::create_dir_all(cache_path.parent().unwrap())?;
+            let file = std::fs::File::create(&cache_path)?;
+            serde_json::to_writer(std::io::BufWriter::new(file), &library)?;
+            Ok(())
+        };
+        if let Err(e) = write() {
+            println!("Couldn't cache the song library: {}", e);
+        }
+        library
+    }
+
+    /// Finds every song folder below `dirs`. Songs that didn't change since `cache` was made
+    /// are taken from it instead of being read again
+    pub fn scan(dirs: &[PathBuf], cache: &Library) -> Self {
+        let mut song_dirs = vec![];
+        for dir in dirs {
+            find_song_dirs(dir, &mut song_dirs);
+        }
+        song_dirs.sort();
+        song_dirs.dedup();
+
+        let mut library = Library {
+            version: CACHE_VERSION,
+            ..Default::default()
+        };
+        for dir in song_dirs {
+            let modified = last_modified(&dir);
+            if let Some(song) = cache
+                .songs
+                .iter()
+                .find(|song| song.dir == dir && song.modified == modified)
+            {
+                library.songs.push(song.clone());
+                continue;
+            }
+            if let Some(broken) = cache
+                .broken
+                .iter()
+                .find(|broken| broken.dir == dir && broken.modified == modified)
+            {
+                library.broken.push(broken.clone());
+                continue;
+            }
+            match read_song(&dir, modified) {
+                Ok(song) => library.songs.push(song),
+                Err(e) => library.broken.push(BrokenSong {
+                    dir,
+                    error: e.to_string(),
+                    modified,
+                }),
+            }
+        }
+        library
+    }
+}
+
+impl Library {
+    /// Song given by folder name, map hash or title, in that order
+    pub fn find(&self, name: &str) -> Option<&LibrarySong> {
+        let find =
+            |matches: &dyn Fn(&LibrarySong) -> bool| self.songs.iter().find(|song| matches(song));
+        find(&|song| song.dir.file_name().map(|dir| dir == name).unwrap_or(false))
+            .or_else(|| find(&|song| song.hash.eq_ignore_ascii_case(name)))
+            .or_else(|| find(&|song| song.title.eq_ignore_ascii_case(name)))
+    }
+
+    /// Folder of the song `name`. Folders given by path and songs the library couldn't read
+    /// are left for the parser to report on
+    pub fn song_dir(&self, dirs: &[PathBuf], name: &str) -> Result<PathBuf, LibraryError> {
+        if let Some(song) = self.find(name) {
+            return Ok(song.dir.clone());
+        }
+        let dir = find_song(dirs, name);
+        if is_song_dir(&dir) {
+            Ok(dir)
+        } else {
+            Err(LibraryError::UnknownSong(name.to_string()))
+        }
+    }
+
+    /// Folder of the song `name` after checking it has the difficulty. Folders given by path
+    /// and songs the library couldn't read are left for the parser to report on
+    pub fn resolve(
+        &self,
+        dirs: &[PathBuf],
+        name: &str,
+        characteristic: &str,
+        difficulty: &str,
+    ) -> Result<PathBuf, LibraryError> {
+        let song = match self.find(name) {
+            Some(song) => song,
+            None => return self.song_dir(dirs, name),
+        };
+        if song.has_difficulty(characteristic, difficulty) {
+            Ok(song.dir.clone())
+        } else {
+            Err(LibraryError::Parse(ParseError::new(
+                &song.dir,
+                "_difficultyBeatmapSets".to_string(),
+                ParseErrorKind::UnknownDifficulty(format!("{} {}", characteristic, difficulty)),
+            )))
+        }
+    }
+}
+
+impl LibrarySong {
+    /// Matches names like `SongInfo::find_beatmap` does
+    pub fn has_difficulty(&self, characteristic: &str, difficulty: &str) -> bool {
+        self.difficulties
+            .iter()
+            .filter(|set| set.characteristic.eq_ignore_ascii_case(characteristic))
+            .flat_map(|set| set.difficulties.iter())
+            .any(|other| other.eq_ignore_ascii_case(difficulty))
+    }
+}
+
+/// Folder of a song given by name, looked up in every song directory in order.
+/// No directories means the default one
+fn find_song(dirs: &[PathBuf], name: &str) -> PathBuf {
+    let default_dir = Path::new(DEFAULT_SONG_DIR).join(name);
+    dirs.iter()
+        .map(|dir| dir.join(name))
+        .find(|dir| dir.is_dir())
+        .or_else(|| dirs.first().map(|dir| dir.join(name)))
+        .unwrap_or(default_dir)
+}
+
+fn is_song_dir(dir: &Path) -> bool {
+    ["Info.dat", "info.dat", "info.json"]
+        .iter()
+        .any(|name| dir.join(name).is_file())
+}
+
+fn find_song_dirs(dir: &Path, song_dirs: &mut Vec<PathBuf>) {
+    if is_song_dir(dir) {
+        song_dirs.push(dir.to_path_buf());
+        return;
+    }
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        if entry.file_type().map(|kind| kind.is_dir()).unwrap_or(false) {
+            find_song_dirs(&entry.path(), song_dirs);
+        }
+    }
+}
+
+fn last_modified(dir: &Path) -> u64 {
+    std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok()?.metadata().ok()?.modified().ok())
+        .filter_map(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|time| time.as_millis() as u64)
+        .max()
+        .unwrap_or(0)
+}
+
+fn read_song(dir: &Path, modified: u64) -> Result<LibrarySong, ParseError> {
+    let info = parser::info::open_info(dir)?;
+    let hash = parser::map_hash(&info)?;
+    if info
+        .difficulty_sets
+        .iter()
+        .all(|set| set.difficulties.is_empty())
+    {
+        return Err(ParseError::new(
+            &info.path,
+            "_difficultyBeatmapSets".to_string(),
+            ParseErrorKind::MissingField,
+        ));
+    }
+    let duration = ogg_duration(&dir.join(&info.song_file));
+    Ok(LibrarySong {
+        dir: dir.to_path_buf(),
+        title: info.song_name,
+        sub_title: info.song_sub_name,
+        artist: info.song_author_name,
+        mapper: info.level_author_name,
+        bpm: info.bpm,
+        duration,
+        preview_start_time: info.preview_start_time,
+        difficulties: info
+            .difficulty_sets
+            .into_iter()
+            .map(|set| LibraryDifficulties {
+                characteristic: set.characteristic,
+                difficulties: set
+                    .difficulties
+                    .into_iter()
+                    .map(|beatmap| beatmap.difficulty)
+                    .collect(),
+            })
+            .collect(),
+        cover: info.cover_image_file.map(|cover| dir.join(cover)),
+        hash,
+        modified,
+    })
+}
+
+/// Length of an Ogg Vorbis file from its headers, without decoding it. The sample rate
+/// is in the first page and the last page holds the total sample count
+fn ogg_duration(path: &Path) -> Option<f32> {
+    // Pages are at most 64 KiB, so the last one starts within that distance from the end
+    const MAX_PAGE_SIZE: u64 = 65_307;
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut head = [0; 58];
+    file.read_exact(&mut head).ok()?;
+    // A page header with a single segment, then the vorbis identification header
+    if &head[0..4] != b"OggS" || &head[28..35] != b"\x01vorbis" {
+        return None;
+    }
+    let sample_rate = u32::from_le_bytes([head[40], head[41], head[42], head[43]]);
+
+    let length = file.seek(SeekFrom::End(0)).ok()?;
+    file.seek(SeekFrom::Start(length.saturating_sub(MAX_PAGE_SIZE)))
+        .ok()?;
+    let mut tail = vec![];
+    file.read_to_end(&mut tail).ok()?;
+    let last_page = (0..tail.len().saturating_sub(14))
+        .rev()
+        .find(|&i| &tail[i..i + 4] == b"OggS" && tail[i + 4] == 0)?;
+    let mut granule = [0; 8];
+    granule.copy_from_slice(&tail[last_page + 6..last_page + 14]);
+    let samples = u64::from_le_bytes(granule);
+    if sample_rate == 0 {
+        return None;
+    }
+    Some(samples as f32 / sample_rate as f32)
+}
+
+/// Prints every song of the library, then the folders that couldn't be read
+pub fn print_library(library: &Library) {
+    let mut songs: Vec<&LibrarySong> = library.songs.iter().collect();
+    songs.sort_by_key(|song| song.title.to_lowercase());
+    for song in songs {
+        let duration = song
+            .duration
+            .map(|duration| {
+                let seconds = duration.round() as u32;
+                format!("{}:{:02}", seconds / 60, seconds % 60)
+            })
+            .unwrap_or_else(|| "?:??".to_string());
+        let title = if song.sub_title.is_empty() {
+            song.title.clone()
+        } else {
+            format!("{} {}", song.title, song.sub_title)
+        };
+        println!(
+            "{} - {}, mapped by {} ({} bpm, {}, preview from {:.1}s)",
+            title, song.artist, song.mapper, song.bpm, duration, song.preview_start_time
+        );
+        println!("  {}  {}", song.dir.display(), song.hash);
+        for set in &song.difficulties {
+            println!("  {}: {}", set.characteristic, set.difficulties.join(", "));
+        }
+    }
+    if !library.broken.is_empty() {
+        println!("{} songs couldn't be read:", library.broken.len());
+    }
+    for broken in &library.broken {
+        println!("  {}: {}", broken.dir.display(), broken.error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures() -> Vec<PathBuf> {
+        vec![Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")]
+    }
+
+    #[test]
+    fn songs_are_found_by_folder_title_or_hash() {
+        let library = Library::scan(&fixtures(), &Library::default());
+        let song = library.find("Lopsided").unwrap();
+        assert_eq!(song.title, "Lopsided Lanes");
+        assert_eq!(song.preview_start_time, 12.5);
+        assert_eq!(library.find("lopsided lanes").unwrap().dir, song.dir);
+        let hash = song.hash.to_uppercase();
+        assert_eq!(library.find(&hash).unwrap().dir, song.dir);
+        assert!(library.find("Lopsided Lane").is_none());
+    }
+
+    #[test]
+    fn resolve_checks_the_difficulty() {
+        let dirs = fixtures();
+        let library = Library::scan(&dirs, &Library::default());
+        let dir = library
+            .resolve(&dirs, "Lopsided Lanes", "360degree", "expert")
+            .unwrap();
+        assert!(dir.ends_with("Lopsided"));
+        match library.resolve(&dirs, "Lopsided", "Standard", "Hard") {
+            Err(LibraryError::Parse(ParseError {
+                kind: ParseErrorKind::UnknownDifficulty(difficulty),
+                ..
+            })) => assert_eq!(difficulty, "Standard Hard"),
+            other => panic!("expected an unknown difficulty, got {:?}", other),
+        }
+        match library.resolve(&dirs, "Missing", "Standard", "Expert") {
+            Err(LibraryError::UnknownSong(name)) => assert_eq!(name, "Missing"),
+            other => panic!("expected an unknown song, got {:?}", other),
+        }
+    }
+}
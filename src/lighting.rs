@@ -0,0 +1,229 @@
+// Colours of the two light colours at full brightness
+pub const LIGHT_RED: [f32; 3] = [0.78, 0.08, 0.08];
+pub const LIGHT_BLUE: [f32; 3] = [0.19, 0.6, 1.0];
+pub const LIGHT_WHITE: [f32; 3] = [1.0, 1.0, 1.0];
+
+pub const LIGHT_GROUPS: usize = 5;
+// Brightness a flash or fade starts with
+const FLASH_BRIGHTNESS: f32 = 1.5;
+const FLASH_MS: f32 = 600.0;
+const FADE_MS: f32 = 1500.0;
+
+// Degrees one ring rotation event turns the rings, the next ring follows a bit later
+const RING_STEP: f32 = 90.0;
+const RING_ROTATION_MS: f32 = 1200.0;
+const RING_DELAY_MS: f32 = 60.0;
+// Space between rings before and after a zoom event
+const RING_SPACING: f32 = 4.0;
+const RING_ZOOMED_SPACING: f32 = 1.5;
+const RING_ZOOM_MS: f32 = 800.0;
+// Degrees per second a laser turns for each step of laser speed
+const LASER_STEP_SPEED: f32 = 20.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LightEventKind {
+    // Back lasers, ring lights, left lasers, right lasers and centre lights
+    Light(usize),
+    RingRotation,
+    RingZoom,
+    LeftLaserSpeed,
+    RightLaserSpeed,
+}
+
+impl LightEventKind {
+    /// Event types shared by the v2 `_type` and the v3 `et` fields
+    pub fn from_raw(event_type: i64) -> Option<Self> {
+        match event_type {
+            0..=4 => Some(LightEventKind::Light(event_type as usize)),
+            8 => Some(LightEventKind::RingRotation),
+            9 => Some(LightEventKind::RingZoom),
+            12 => Some(LightEventKind::LeftLaserSpeed),
+            13 => Some(LightEventKind::RightLaserSpeed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct LightEvent {
+    // Song time in ms
+    pub time: f32,
+    pub kind: LightEventKind,
+    pub value: i64,
+    // Brightness of light events, 1.0 when the map doesn't give one
+    pub float_value: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Effect {
+    Off,
+    On,
+    // Brighter for a moment, then on
+    Flash,
+    // Brighter for a moment, then fading out
+    Fade,
+}
+
+/// Lights of one group, changed by its light events
+#[derive(Clone, Copy, Debug)]
+pub struct LightGroup {
+    color: [f32; 3],
+    brightness: f32,
+    effect: Effect,
+    // Song time of the last event
+    since: f32,
+}
+
+impl LightGroup {
+    pub fn new(color: [f32; 3]) -> Self {
+        Self {
+            color,
+            brightness: 1.0,
+            // Maps without light events and the menu keep the lights on
+            effect: Effect::On,
+            since: 0.0,
+        }
+    }
+
+    /// Light values are 0 for off, then on, flash, fade and transition for blue (1-4),
+    /// red (5-8) and white (9-12). Transitions are shown as on
+    pub fn apply(&mut self, event: &LightEvent, colors: &[[f32; 3]; 2]) {
+        self.since = event.time;
+        self.brightness = event.float_value;
+        if event.value <= 0 {
+            self.effect = Effect::Off;
+            return;
+        }
+        let value = event.value - 1;
+        self.color = match value / 4 {
+            0 => colors[1],
+            1 => colors[0],
+            _ => LIGHT_WHITE,
+        };
+        self.effect = match value % 4 {
+            1 => Effect::Flash,
+            2 => Effect::Fade,
+            _ => Effect::On,
+        };
+    }
+
+    pub fn intensity(&self, time: f32) -> f32 {
+        let elapsed = (time - self.since).max(0.0);
+        let flash = |duration: f32, to: f32| {
+            let t = (elapsed / duration).min(1.0);
+            FLASH_BRIGHTNESS + (to - FLASH_BRIGHTNESS) * t
+        };
+        let intensity = match self.effect {
+            Effect::Off => 0.0,
+            Effect::On => 1.0,
+            Effect::Flash => flash(FLASH_MS, 1.0),
+            Effect::Fade => flash(FADE_MS, 0.0),
+        };
+        intensity * self.brightness
+    }
+
+    /// Colour with the intensity in alpha, the colour itself isn't scaled
+    pub fn color(&self, time: f32) -> [f32; 4] {
+        let [r, g, b] = self.color;
+        [r, g, b, self.intensity(time)]
+    }
+}
+
+/// Rotation of the rings, each event turns them one step further with a delay between rings
+#[derive(Clone, Debug)]
+pub struct Rings {
+    // Rotation of rings whose every event has finished, and the events still turning them
+    settled: f32,
+    rotations: Vec<(f32, f32)>,
+    turns: u32,
+    zoom_from: f32,
+    zoom_to: f32,
+    zoom_time: f32,
+}
+
+impl Default for Rings {
+    fn default() -> Self {
+        Self {
+            settled: 0.0,
+            rotations: vec![],
+            turns: 0,
+            zoom_from: RING_SPACING,
+            zoom_to: RING_SPACING,
+            zoom_time: 0.0,
+        }
+    }
+}
+
+impl Rings {
+    pub fn rotate(&mut self, time: f32) {
+        // Turns alternate between both directions. `is_multiple_of` needs a newer compiler
+        #[allow(clippy::manual_is_multiple_of)]
+        let step = if self.turns % 2 == 0 {
+            RING_STEP
+        } else {
+            -RING_STEP
+        };
+        self.turns += 1;
+        self.rotations.push((time, step));
+    }
+
+    pub fn zoom(&mut self, time: f32) {
+        self.zoom_from = self.spacing(time);
+        self.zoom_to = if self.zoom_to == RING_SPACING {
+            RING_ZOOMED_SPACING
+        } else {
+            RING_SPACING
+        };
+        self.zoom_time = time;
+    }
+
+    /// Forgets rotations that finished for every ring of `ring_count`
+    pub fn settle(&mut self, time: f32, ring_count: usize) {
+        let finished = RING_ROTATION_MS + RING_DELAY_MS * ring_count as f32;
+        let settled = &mut self.settled;
+        self.rotations.retain(|&(start, step)| {
+            let done = time - start > finished;
+            if done {
+                *settled += step;
+            }
+            !done
+        });
+    }
+
+    /// Rotation of ring `index` in degrees
+    pub fn angle(&self, index: usize, time: f32) -> f32 {
+        let delay = RING_DELAY_MS * index as f32;
+        self.settled
+            + self
+                .rotations
+                .iter()
+                .map(|&(start, step)| {
+                    let t = ((time - start - delay) / RING_ROTATION_MS).clamp(0.0, 1.0);
+                    // Slows down towards the end of the turn
+                    step * (1.0 - (1.0 - t) * (1.0 - t))
+                })
+                .sum::<f32>()
+    }
+
+    pub fn spacing(&self, time: f32) -> f32 {
+        let t = ((time - self.zoom_time) / RING_ZOOM_MS).clamp(0.0, 1.0);
+        self.zoom_from + (self.zoom_to - self.zoom_from) * t
+    }
+}
+
+/// Rotation of one side of lasers, their speed comes from laser speed events
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Lasers {
+    pub angle: f32,
+    speed: f32,
+}
+
+impl Lasers {
+    pub fn set_speed(&mut self, value: i64) {
+        self.speed = value as f32 * LASER_STEP_SPEED;
+    }
+
+    pub fn update(&mut self, elapsed_ms: f32) {
+        self.angle = (self.angle + self.speed * elapsed_ms / 1000.0) % 360.0;
+    }
+}
@@ -3,16 +3,31 @@ extern crate glium;
 #[macro_use]
 extern crate specs_derive;
 
+mod arcs;
+mod autoplay;
+mod bench;
+mod collision;
+mod colors;
 mod components;
+mod game;
+mod headless;
+mod highscores;
+mod lanes;
+mod library;
+mod lighting;
+mod modifiers;
 mod obj_loader;
 mod openxr_module;
 mod parser;
 mod render;
+mod replay;
+mod scoring;
 mod songs;
 mod textures;
+mod timing;
 
-use specs::World;
-use clap::{Arg, App};
+use specs::{RunNow, World};
+use clap::{Arg, App, SubCommand};
 
 fn main() {
     println!("Starting SlashMania");
@@ -25,39 +40,249 @@ fn main() {
             .short("d")
             .value_name("DIFFICULTY")
             .takes_value(true))
+        .arg(Arg::with_name("characteristic")
+            .short("c")
+            .value_name("CHARACTERISTIC")
+            .takes_value(true))
+        .arg(Arg::with_name("njs")
+            .long("njs")
+            .value_name("NOTE_JUMP_SPEED")
+            .help("Overrides note jump speed of the map")
+            .takes_value(true))
+        .arg(Arg::with_name("modifiers")
+            .short("m")
+            .long("modifiers")
+            .value_name("MODIFIERS")
+            .help("Comma separated gameplay modifiers: nf, nb, nw, na, da, gn, fs, ss, if, be, sa, zm, mr")
+            .takes_value(true))
+        .arg(Arg::with_name("autoplay")
+            .long("autoplay")
+            .help("Lets a bot play the song perfectly"))
+        .arg(Arg::with_name("replay")
+            .long("replay")
+            .value_name("FILE")
+            .help("Watches a replay, or checks its score together with --headless")
+            .conflicts_with("autoplay")
+            .takes_value(true))
+        .arg(Arg::with_name("songs")
+            .long("songs")
+            .value_name("DIR")
+            .help("Folder with songs, can be given more than once. Defaults to ./assets/songs")
+            .multiple(true)
+            .number_of_values(1)
+            .takes_value(true))
+        .arg(Arg::with_name("colors")
+            .long("colors")
+            .value_name("PRESET")
+            .help("Colour scheme of notes, lights and walls: default, sunset, forest, contrast")
+            .takes_value(true))
+        .arg(Arg::with_name("no-map-colors")
+            .long("no-map-colors")
+            .help("Keeps the colour scheme when a map brings its own colours"))
+        .arg(Arg::with_name("headless")
+            .long("headless")
+            .help("Plays the song with the bot or a replay without headset and audio and prints the results"))
+        .subcommand(SubCommand::with_name("bench")
+            .about("Measures gameplay frame time on a generated dense map")
+            .arg(Arg::with_name("notes")
+                .long("notes")
+                .value_name("NOTES")
+                .takes_value(true))
+            .arg(Arg::with_name("nps")
+                .long("nps")
+                .value_name("NOTES_PER_SECOND")
+                .takes_value(true)))
+        .subcommand(SubCommand::with_name("scores")
+            .about("Lists personal bests, or every run of one song with -s"))
+        .subcommand(SubCommand::with_name("library")
+            .about("Scans the song folders and lists every song found"))
+        .subcommand(SubCommand::with_name("hash")
+            .about("Prints the BeatSaver hash of a song folder")
+            .arg(Arg::with_name("SONG")
+                .help("Song folder, or the folder name or title of a song in the song folders")
+                .required(true)))
         .get_matches();
 
+    if let Some(bench) = matches.subcommand_matches("bench") {
+        let notes = bench.value_of("notes").unwrap_or("5000").parse().expect("Note count should be a number");
+        let notes_per_second = bench.value_of("nps").unwrap_or("20").parse().expect("Notes per second should be a number");
+        bench::run_spawn_benchmark(notes, notes_per_second);
+        return;
+    }
+    let song_dirs: Vec<std::path::PathBuf> = match matches.values_of("songs") {
+        Some(dirs) => dirs.map(std::path::PathBuf::from).collect(),
+        None => vec![std::path::PathBuf::from(library::DEFAULT_SONG_DIR)],
+    };
+    if matches.subcommand_matches("library").is_some() {
+        library::print_library(&library::Library::load(&song_dirs));
+        return;
+    }
+    if let Some(hash) = matches.subcommand_matches("hash") {
+        let song = hash.value_of("SONG").unwrap();
+        let song_dir = if std::path::Path::new(song).is_dir() {
+            Ok(std::path::PathBuf::from(song))
+        } else {
+            library::Library::load(&song_dirs).song_dir(&song_dirs, song)
+        };
+        let hash = song_dir.and_then(|song_dir| {
+            let info = parser::info::open_info(&song_dir)?;
+            Ok(parser::map_hash(&info)?)
+        });
+        match hash {
+            Ok(hash) => println!("{}", hash),
+            Err(e) => {
+                println!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if matches.subcommand_matches("scores").is_some() {
+        // Scores are kept under the folder name, titles and hashes are looked up in the library
+        let library = library::Library::load(&song_dirs);
+        let song = matches.value_of("song").map(|name| {
+            library
+                .find(name)
+                .and_then(|song| song.dir.file_name())
+                .map(|folder| folder.to_string_lossy().into_owned())
+                .unwrap_or_else(|| name.to_string())
+        });
+        highscores::print_scores(song.as_deref());
+        return;
+    }
+
     let song_name = matches.value_of("song").unwrap_or("Test Song").to_string();
     let difficulty = matches.value_of("difficulty").unwrap_or("Expert").to_string();
+    let characteristic = matches.value_of("characteristic").unwrap_or("Standard").to_string();
+    let note_jump_speed = matches
+        .value_of("njs")
+        .map(|njs| njs.parse::<f32>().expect("Note jump speed should be a number"));
+    let modifiers = match modifiers::Modifiers::from_list(matches.value_of("modifiers").unwrap_or("")) {
+        Ok(modifiers) => modifiers,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+
+    let replay = match matches.value_of("replay").map(|path| replay::Replay::load(std::path::Path::new(path))) {
+        Some(Ok(replay)) => Some(replay),
+        Some(Err(e)) => {
+            println!("Couldn't read the replay: {}", e);
+            return;
+        }
+        None => None,
+    };
+    let color_scheme = match matches.value_of("colors").map(|name| (name, colors::preset(name))) {
+        Some((_, Some(colors))) => colors,
+        Some((name, None)) => {
+            println!("Unknown colour scheme: {}", name);
+            return;
+        }
+        None => Default::default(),
+    };
+    // Checked before the headset starts, replays are checked when their song loads
+    if replay.is_none() {
+        let library = library::Library::load(&song_dirs);
+        if let Err(e) = library.resolve(&song_dirs, &song_name, &characteristic, &difficulty) {
+            println!("{}", e);
+            for set in library.find(&song_name).iter().flat_map(|song| song.difficulties.iter()) {
+                println!("  {}: {}", set.characteristic, set.difficulties.join(", "));
+            }
+            return;
+        }
+    }
+    let autoplay_enabled = matches.is_present("autoplay");
+    if matches.is_present("headless") {
+        match &replay {
+            Some(replay) => {
+                if !headless::rescore(replay, &song_dirs) {
+                    std::process::exit(1);
+                }
+            }
+            None if autoplay_enabled => headless::play(game::SongSelection {
+                name: song_name,
+                characteristic,
+                difficulty,
+                modifiers,
+            }, note_jump_speed, &song_dirs),
+            None => println!("--headless needs --autoplay or --replay"),
+        }
+        return;
+    }
+    let (selection, note_jump_speed) = match &replay {
+        Some(replay) => {
+            let modifiers = match modifiers::Modifiers::from_list(&replay.modifiers) {
+                Ok(modifiers) => modifiers,
+                Err(e) => {
+                    println!("Invalid replay: {}", e);
+                    return;
+                }
+            };
+            (game::SongSelection {
+                name: replay.song.clone(),
+                characteristic: replay.characteristic.clone(),
+                difficulty: replay.difficulty.clone(),
+                modifiers,
+            }, Some(replay.note_jump_speed))
+        }
+        None => (game::SongSelection {
+            name: song_name,
+            characteristic,
+            difficulty,
+            modifiers,
+        }, note_jump_speed),
+    };
 
     let mut world = World::new();
     components::register_default(&mut world);
+    {
+        let mut settings = world.write_resource::<components::PlayerSettings>();
+        settings.note_jump_speed = note_jump_speed;
+        settings.autoplay = autoplay_enabled;
+        settings.song_dirs = song_dirs;
+        settings.color_scheme = color_scheme;
+        settings.ignore_map_colors = matches.is_present("no-map-colors");
+    }
+    songs::set_color_scheme(color_scheme, &mut world);
 
     let mut window = render::Window::new();
     window.compile_shaders();
     window.load_default_models();
     window.load_default_textures();
 
-    let mut dispatcher = specs::DispatcherBuilder::new()
-        .with(components::sound::SoundSystem::new(), "Sound System", &[])
-        .with(components::note::NoteSystem{..Default::default()}, "Note System", &[])
-        .with(components::obstacle::ObstacleSystem{..Default::default()}, "Obstacle System", &[])
-        .with_thread_local(window)
-        .build();
-
-    let song = songs::load_song(song_name, difficulty, &mut world);
-    match song {
-        Err(e) => println!("Error while loading song: {}", e),
-        _ => {}
-    };
-    'main: loop {
-        dispatcher.dispatch(&mut world.res);
-        let ents_to_remove = {
-            let ents_to_remove_raw = &mut world.write_resource::<components::RemoveEntities>().0;
-            let ents_to_remove = ents_to_remove_raw.clone();
-            ents_to_remove_raw.clear();
-            ents_to_remove
+    components::saber::create_sabers(&mut world);
+    components::lights::create_environment(&mut world);
+    let mut sound = components::sound::SoundSystem::new();
+    let mut sabers = components::saber::SaberSystem;
+    let mut autoplay = components::autoplay::AutoplaySystem::default();
+    let mut game = game::Game::new(&mut world);
+    let mut playback = replay.map(|replay| {
+        *world.write_resource::<components::replay::ReplayPlayback>() = components::replay::ReplayPlayback {
+            frames: replay.frames,
+            map_hash: Some(replay.map_hash),
         };
-        world.delete_entities(&ents_to_remove).unwrap();
+        // Behind the player and a bit to the right
+        world.write_resource::<render::SpectatorCamera>().offset = Some(nalgebra::Isometry3::translation(-1.0, 0.3, -2.5));
+        components::replay::ReplayPlaybackSystem::new(components::replay::create_head(&mut world))
+    });
+    world.write_resource::<game::StateEvents>().queue.push(game::StateEvent::SelectSong(selection));
+    loop {
+        game.update(&mut world);
+        if game.state == game::GameState::Exiting {
+            break;
+        }
+        sound.run_now(&world.res);
+        if autoplay_enabled {
+            autoplay.run_now(&world.res);
+        }
+        if let Some(playback) = &mut playback {
+            playback.run_now(&world.res);
+        }
+        sabers.run_now(&world.res);
+        game.dispatch(&world);
+        window.run_now(&world.res);
+        components::maintain(&mut world);
     }
+    println!("Exiting SlashMania");
 }
@@ -0,0 +1,335 @@
+use crate::components::note::{Direction, NoteType};
+use crate::parser::ParsedSong;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SongSpeed {
+    #[default]
+    Normal,
+    Faster,
+    Slower,
+}
+
+impl SongSpeed {
+    pub fn ratio(&self) -> f32 {
+        match self {
+            SongSpeed::Normal => 1.0,
+            SongSpeed::Faster => 1.2,
+            SongSpeed::Slower => 0.85,
+        }
+    }
+}
+
+/// Gameplay modifiers of the song that is played. The game has no menu yet, they come from
+/// `--modifiers` on the command line or from the replay that is played back
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Modifiers {
+    pub no_fail: bool,
+    pub no_bombs: bool,
+    pub no_walls: bool,
+    pub no_arrows: bool,
+    pub disappearing_arrows: bool,
+    pub ghost_notes: bool,
+    pub song_speed: SongSpeed,
+    pub instafail: bool,
+    pub battery_energy: bool,
+    pub strict_angles: bool,
+    // No objects at all, the song just plays
+    pub zen_mode: bool,
+    // Left handed mode, swaps colours and mirrors the map
+    pub mirror: bool,
+}
+
+impl Modifiers {
+    /// Parses a comma separated list like `nf,da` or `no-fail,disappearing-arrows`
+    pub fn from_list(list: &str) -> Result<Self, String> {
+        let mut modifiers = Self::default();
+        for name in list
+            .split(',')
+            .map(|name| name.trim())
+            .filter(|name| !name.is_empty())
+        {
+            let key: String = name
+                .to_lowercase()
+                .chars()
+                .filter(|c| *c != '-' && *c != '_')
+                .collect();
+            match key.as_str() {
+                "nf" | "nofail" => modifiers.no_fail = true,
+                "nb" | "nobombs" => modifiers.no_bombs = true,
+                "nw" | "no" | "nowalls" | "noobstacles" => modifiers.no_walls = true,
+                "na" | "noarrows" => modifiers.no_arrows = true,
+                "da" | "disappearingarrows" => modifiers.disappearing_arrows = true,
+                "gn" | "ghostnotes" => modifiers.ghost_notes = true,
+                "fs" | "fastersong" => modifiers.song_speed = SongSpeed::Faster,
+                "ss" | "slowersong" => modifiers.song_speed = SongSpeed::Slower,
+                "if" | "instafail" => modifiers.instafail = true,
+                "be" | "batteryenergy" => modifiers.battery_energy = true,
+                "sa" | "strictangles" => modifiers.strict_angles = true,
+                "zm" | "zen" | "zenmode" => modifiers.zen_mode = true,
+                "mr" | "mirror" | "lefthanded" => modifiers.mirror = true,
+                _ => return Err(format!("Unknown modifier {}", name)),
+            }
+        }
+        Ok(modifiers)
+    }
+
+    /// Short codes of the enabled modifiers, read back by `from_list`
+    pub fn to_list(self) -> String {
+        let codes: Vec<&str> = [
+            (self.no_fail, "nf"),
+            (self.no_bombs, "nb"),
+            (self.no_walls, "nw"),
+            (self.no_arrows, "na"),
+            (self.disappearing_arrows, "da"),
+            (self.ghost_notes, "gn"),
+            (self.song_speed == SongSpeed::Faster, "fs"),
+            (self.song_speed == SongSpeed::Slower, "ss"),
+            (self.instafail, "if"),
+            (self.battery_energy, "be"),
+            (self.strict_angles, "sa"),
+            (self.zen_mode, "zm"),
+            (self.mirror, "mr"),
+        ]
+        .iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, code)| *code)
+        .collect();
+        codes.join(",")
+    }
+
+    /// Factor applied to the score, easier modifiers lower it
+    pub fn score_multiplier(&self) -> f32 {
+        if self.zen_mode {
+            return 0.0;
+        }
+        let mut multiplier = 1.0;
+        for (enabled, change) in &[
+            (self.no_fail, -0.5),
+            (self.no_bombs, -0.1),
+            (self.no_walls, -0.05),
+            (self.no_arrows, -0.3),
+            (self.ghost_notes, 0.11),
+            (self.disappearing_arrows, 0.07),
+            (self.song_speed == SongSpeed::Faster, 0.08),
+            (self.song_speed == SongSpeed::Slower, -0.3),
+        ] {
+            if *enabled {
+                multiplier += change;
+            }
+        }
+        f32::max(multiplier, 0.0)
+    }
+
+    /// Removes and changes map objects before the song starts
+    pub fn apply(&self, song: &mut ParsedSong) {
+        if self.zen_mode {
+            song.notes.clear();
+            song.obstacles.clear();
+            song.arcs.clear();
+        }
+        if self.no_bombs {
+            song.notes
+                .retain(|note| !matches!(note.note_type, NoteType::Mine));
+        }
+        if self.no_walls {
+            song.obstacles.clear();
+        }
+        if self.no_arrows {
+            for note in &mut song.notes {
+                note.direction = Direction::NoDirection;
+                note.angle_offset = 0;
+            }
+        }
+        if self.mirror {
+            mirror(song);
+        }
+    }
+}
+
+/// Mirrors a map left to right and swaps the colours. Mirroring twice gives back the original
+pub fn mirror(song: &mut ParsedSong) {
+    for note in &mut song.notes {
+        note.note_type = match note.note_type {
+            NoteType::Red => NoteType::Blue,
+            NoteType::Blue => NoteType::Red,
+            NoteType::Mine => NoteType::Mine,
+        };
+        // Wraps for lanes outside the grid so those still round trip
+        note.line_index = 3u8.wrapping_sub(note.line_index);
+        note.direction = mirror_direction(note.direction);
+        note.angle_offset = -note.angle_offset;
+        note.rotation = -note.rotation;
+        if let Some(link) = &mut note.link {
+            link.x = 3.0 - link.x;
+            link.angle = -link.angle;
+        }
+    }
+    for arc in &mut song.arcs {
+        arc.note_type = match arc.note_type {
+            NoteType::Red => NoteType::Blue,
+            _ => NoteType::Red,
+        };
+        arc.line_index = 3 - arc.line_index;
+        arc.tail_line_index = 3 - arc.tail_line_index;
+        arc.direction = mirror_direction(arc.direction);
+        arc.tail_direction = mirror_direction(arc.tail_direction);
+        arc.rotation = -arc.rotation;
+    }
+    for obstacle in &mut song.obstacles {
+        // The rightmost lane of the wall becomes its leftmost one
+        obstacle.line_index = 4 - (obstacle.line_index + obstacle.width);
+        obstacle.rotation = -obstacle.rotation;
+    }
+    song.lane_rotations.mirror();
+}
+
+fn mirror_direction(direction: Direction) -> Direction {
+    match direction {
+        Direction::Left => Direction::Right,
+        Direction::Right => Direction::Left,
+        Direction::TopLeft => Direction::TopRight,
+        Direction::TopRight => Direction::TopLeft,
+        Direction::BottomLeft => Direction::BottomRight,
+        Direction::BottomRight => Direction::BottomLeft,
+        Direction::Top => Direction::Top,
+        Direction::Bottom => Direction::Bottom,
+        Direction::NoDirection => Direction::NoDirection,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scoring::ScoringType;
+    use std::path::Path;
+
+    type NoteFields = (u8, u8, NoteType, f32, Direction, i32, f32, ScoringType);
+    type ArcFields = (NoteType, u8, u8, Direction, u8, u8, Direction, f32);
+
+    fn open_fixture(characteristic: &str) -> ParsedSong {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/Lopsided");
+        crate::parser::open_song(&dir, characteristic, "Expert")
+            .unwrap()
+            .1
+    }
+
+    fn notes(song: &ParsedSong) -> Vec<NoteFields> {
+        song.notes
+            .iter()
+            .map(|note| {
+                (
+                    note.line_layer,
+                    note.line_index,
+                    note.note_type,
+                    note.time,
+                    note.direction,
+                    note.angle_offset,
+                    note.rotation,
+                    note.scoring,
+                )
+            })
+            .collect()
+    }
+
+    // Chain links sit between the lanes, `3.0 - x` twice can be off in the last bit
+    fn assert_same_links(song: &ParsedSong, original: &ParsedSong) {
+        for (note, original) in song.notes.iter().zip(&original.notes) {
+            match (note.link, original.link) {
+                (Some(link), Some(original)) => {
+                    assert!((link.x - original.x).abs() < 1e-5);
+                    assert_eq!((link.y, link.angle), (original.y, original.angle));
+                }
+                (link, original) => assert_eq!(link.is_some(), original.is_some()),
+            }
+        }
+    }
+
+    fn obstacles(song: &ParsedSong) -> Vec<(i32, i32, i32, i32, f32, f32, f32)> {
+        song.obstacles
+            .iter()
+            .map(|obstacle| {
+                (
+                    obstacle.line_index,
+                    obstacle.line_layer,
+                    obstacle.width,
+                    obstacle.height,
+                    obstacle.time,
+                    obstacle.duration,
+                    obstacle.rotation,
+                )
+            })
+            .collect()
+    }
+
+    fn arcs(song: &ParsedSong) -> Vec<(ArcFields, f32)> {
+        song.arcs
+            .iter()
+            .map(|arc| {
+                (
+                    (
+                        arc.note_type,
+                        arc.line_index,
+                        arc.line_layer,
+                        arc.direction,
+                        arc.tail_line_index,
+                        arc.tail_line_layer,
+                        arc.tail_direction,
+                        arc.time,
+                    ),
+                    arc.rotation,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn mirroring_twice_gives_back_the_map() {
+        for characteristic in &["Standard", "360Degree"] {
+            let original = open_fixture(characteristic);
+            let mut song = open_fixture(characteristic);
+            assert!(song.notes.iter().any(|note| note.link.is_some()));
+            assert!(!song.arcs.is_empty() && !song.obstacles.is_empty());
+
+            mirror(&mut song);
+            assert_ne!(notes(&song), notes(&original));
+            assert_ne!(obstacles(&song), obstacles(&original));
+            assert_ne!(arcs(&song), arcs(&original));
+
+            mirror(&mut song);
+            assert_eq!(notes(&song), notes(&original));
+            assert_same_links(&song, &original);
+            assert_eq!(obstacles(&song), obstacles(&original));
+            assert_eq!(arcs(&song), arcs(&original));
+            for beat in 0..12 {
+                let time = original.tempo_map.beat_to_ms(beat as f32);
+                assert_eq!(
+                    song.lane_rotations.rotation_at(time),
+                    original.lane_rotations.rotation_at(time)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn mirror_swaps_colours_and_sides() {
+        let mut song = open_fixture("360Degree");
+        // The red bottom left note on the first turn of the lane
+        let note = &song.notes[2];
+        assert_eq!((note.note_type, note.line_index), (NoteType::Red, 1));
+        assert_eq!(
+            (note.direction, note.angle_offset),
+            (Direction::BottomLeft, -30)
+        );
+        assert_eq!(note.rotation, 15.0);
+        mirror(&mut song);
+        let note = &song.notes[2];
+        assert_eq!((note.note_type, note.line_index), (NoteType::Blue, 2));
+        assert_eq!(
+            (note.direction, note.angle_offset),
+            (Direction::BottomRight, 30)
+        );
+        assert_eq!(note.rotation, -15.0);
+        // The wall over the left lane moves to the right one
+        assert_eq!(song.obstacles[0].line_index, 3);
+    }
+}
@@ -78,3 +78,20 @@ pub fn box_vertex_buf<F: Facade + ?Sized>(context: &F) -> VertexBufferAny {
     .unwrap()
     .into_vertex_buffer_any()
 }
+
+pub fn ribbon_vertex_buf<F: Facade + ?Sized>(
+    ribbon: &[crate::arcs::RibbonVertex],
+    context: &F,
+) -> VertexBufferAny {
+    let vertex_data: Vec<Vertex> = ribbon
+        .iter()
+        .map(|vertex| Vertex {
+            position: vertex.position,
+            normal: vertex.normal,
+            tex_coords: vertex.tex_coords,
+        })
+        .collect();
+    VertexBuffer::new(context, &vertex_data)
+        .unwrap()
+        .into_vertex_buffer_any()
+}
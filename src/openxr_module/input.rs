@@ -0,0 +1,178 @@
+use crate::components::input::{HandInput, HapticEvent};
+use crate::openxr_module::xrmath;
+use openxr as xr;
+
+const HANDS: [&str; 2] = ["/user/hand/left", "/user/hand/right"];
+
+// Interaction profile, trigger input and menu inputs of the left and the right hand.
+// Touch controllers only have a menu button on the left hand
+const PROFILES: [(&str, &str, [Option<&str>; 2]); 3] = [
+    (
+        "/interaction_profiles/khr/simple_controller",
+        "select/click",
+        [Some("menu/click"), Some("menu/click")],
+    ),
+    (
+        "/interaction_profiles/oculus/touch_controller",
+        "trigger/value",
+        [Some("menu/click"), None],
+    ),
+    (
+        "/interaction_profiles/valve/index_controller",
+        "trigger/value",
+        [Some("b/click"), Some("b/click")],
+    ),
+];
+
+pub struct Input {
+    action_set: xr::ActionSet,
+    trigger: xr::Action<f32>,
+    menu: xr::Action<bool>,
+    haptic: xr::Action<xr::Haptic>,
+    hand_paths: [xr::Path; 2],
+    grip_spaces: Vec<xr::Space>,
+    aim_spaces: Vec<xr::Space>,
+}
+
+impl Input {
+    pub fn new(instance: &xr::Instance, session: &xr::Session<xr::OpenGL>) -> Self {
+        let hand_paths = [path(instance, HANDS[0]), path(instance, HANDS[1])];
+        let action_set = session
+            .create_action_set("gameplay", "Gameplay", 0)
+            .unwrap();
+        let grip_pose = action_set
+            .create_action::<xr::Posef>("grip_pose", "Grip Pose", &hand_paths)
+            .unwrap();
+        let aim_pose = action_set
+            .create_action::<xr::Posef>("aim_pose", "Aim Pose", &hand_paths)
+            .unwrap();
+        let trigger = action_set
+            .create_action::<f32>("trigger", "Trigger", &hand_paths)
+            .unwrap();
+        let menu = action_set
+            .create_action::<bool>("menu", "Menu", &hand_paths)
+            .unwrap();
+        let haptic = action_set
+            .create_action::<xr::Haptic>("haptic", "Haptic Feedback", &hand_paths)
+            .unwrap();
+
+        for (profile, trigger_input, menu_inputs) in PROFILES.iter() {
+            let mut bindings = vec![];
+            for (hand, hand_path) in HANDS.iter().enumerate() {
+                let input = |input: &str| path(instance, &format!("{}/{}", hand_path, input));
+                bindings.push(xr::Binding::new(&grip_pose, input("input/grip/pose")));
+                bindings.push(xr::Binding::new(&aim_pose, input("input/aim/pose")));
+                bindings.push(xr::Binding::new(
+                    &trigger,
+                    input(&format!("input/{}", trigger_input)),
+                ));
+                if let Some(menu_input) = menu_inputs[hand] {
+                    bindings.push(xr::Binding::new(
+                        &menu,
+                        input(&format!("input/{}", menu_input)),
+                    ));
+                }
+                bindings.push(xr::Binding::new(&haptic, input("output/haptic")));
+            }
+            if let Err(e) = session
+                .set_interaction_profile_suggested_bindings(path(instance, profile), &bindings)
+            {
+                println!("XR: Cannot suggest bindings for {}: {}", profile, e);
+            }
+        }
+
+        let identity = xr::Posef {
+            position: xr::Vector3f {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            orientation: xr::Quaternionf {
+                w: 1.0,
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+        };
+        let grip_spaces = hand_paths
+            .iter()
+            .map(|hand| grip_pose.create_space(*hand, identity).unwrap())
+            .collect();
+        let aim_spaces = hand_paths
+            .iter()
+            .map(|hand| aim_pose.create_space(*hand, identity).unwrap())
+            .collect();
+
+        Self {
+            action_set,
+            trigger,
+            menu,
+            haptic,
+            hand_paths,
+            grip_spaces,
+            aim_spaces,
+        }
+    }
+
+    /// Reads buttons and locates both controllers relative to `base` at `time`
+    pub fn update(
+        &self,
+        session: &xr::Session<xr::OpenGL>,
+        base: &xr::Space,
+        time: xr::Time,
+    ) -> [HandInput; 2] {
+        let mut hands = [HandInput::default(); 2];
+        if session
+            .sync_action_data(&[xr::ActiveActionSet::new(&self.action_set)])
+            .is_err()
+        {
+            return hands;
+        }
+        for (index, hand) in hands.iter_mut().enumerate() {
+            let hand_path = [self.hand_paths[index]];
+            hand.grip = locate(&self.grip_spaces[index], base, time);
+            hand.aim = locate(&self.aim_spaces[index], base, time);
+            if let Ok(trigger) = self.trigger.state(&hand_path) {
+                hand.trigger = trigger.current_state;
+            }
+            if let Ok(menu) = self.menu.state(&hand_path) {
+                hand.menu_pressed = menu.current_state && menu.changed_since_last_sync;
+            }
+        }
+        hands
+    }
+
+    pub fn apply_haptics(&self, events: &[HapticEvent]) {
+        for event in events {
+            let vibration = xr::HapticVibration::new()
+                .amplitude(event.amplitude)
+                // Zero lets the runtime pick the frequency
+                .frequency(0.0)
+                .duration(xr::Duration::from_raw(
+                    (event.duration_ms * 1_000_000.0) as i64,
+                ));
+            let _ = self
+                .haptic
+                .apply_feedback(&[self.hand_paths[event.hand as usize]], &vibration);
+        }
+    }
+}
+
+/// Pose of `space` in playfield coordinates if the runtime knows it
+pub fn locate(
+    space: &xr::Space,
+    base: &xr::Space,
+    time: xr::Time,
+) -> Option<nalgebra::Isometry3<f32>> {
+    let relation = space.locate(base, time).ok()?;
+    let valid = xr::SpaceRelationFlags::POSITION_VALID | xr::SpaceRelationFlags::ORIENTATION_VALID;
+    if relation.relation_flags.contains(valid) {
+        Some(xrmath::pose_to_world(relation.pose))
+    } else {
+        None
+    }
+}
+
+fn path(instance: &xr::Instance, path: &str) -> xr::Path {
+    unsafe { instance.string_to_path(path) }.unwrap()
+}
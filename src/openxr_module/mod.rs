@@ -1,3 +1,4 @@
+pub mod input;
 pub mod xrmath;
 
 use openxr as xr;
@@ -115,6 +116,7 @@ pub struct OpenXR {
     pub views: Vec<xr::View>,
     frame_stream: xr::FrameStream<xr::OpenGL>,
     predicted_display_time: xr::Time,
+    input: input::Input,
 }
 
 impl OpenXR {
@@ -151,6 +153,7 @@ impl OpenXR {
             .unwrap();
 
         let spaces = init_spaces(&session);
+        let input = input::Input::new(&instance, &session);
 
         let view_configuration_views = instance
             .enumerate_view_configuration_views(system, xr::ViewConfigurationType::PRIMARY_STEREO)
@@ -172,6 +175,7 @@ impl OpenXR {
             predicted_display_time: xr::Time::from_raw(0),
             swapchain: Swapchain::empty(),
             views: Vec::with_capacity(4),
+            input,
         }
     }
 
@@ -209,6 +213,9 @@ impl OpenXR {
                                 self.recreate_swapchain()
                             }
                         }
+                        xr::SessionState::STOPPING => {
+                            self.session.end().unwrap();
+                        }
                         _ => {}
                     }
                 }
@@ -252,6 +259,36 @@ impl OpenXR {
             .unwrap();
     }
     pub fn finish_session(&self) {}
+    pub fn update_input(&self, poses: &mut crate::components::input::TrackedPoses) {
+        let stage = self.spaces.0.as_ref().unwrap();
+        poses.head = self
+            .spaces
+            .1
+            .as_ref()
+            .and_then(|view| input::locate(view, stage, self.predicted_display_time));
+        poses.hands = self
+            .input
+            .update(&self.session, stage, self.predicted_display_time);
+    }
+    pub fn apply_haptics(&self, events: &[crate::components::input::HapticEvent]) {
+        self.input.apply_haptics(events);
+    }
+    /// Frames may only be submitted while the session is running
+    pub fn is_running(&self) -> bool {
+        matches!(
+            self.session_state,
+            xr::SessionState::RUNNING | xr::SessionState::VISIBLE | xr::SessionState::FOCUSED
+        )
+    }
+    pub fn is_focused(&self) -> bool {
+        self.session_state == xr::SessionState::FOCUSED
+    }
+    pub fn should_exit(&self) -> bool {
+        matches!(
+            self.session_state,
+            xr::SessionState::EXITING | xr::SessionState::LOSS_PENDING
+        )
+    }
 }
 
 pub fn init_spaces(session: &xr::Session<xr::OpenGL>) -> (Option<xr::Space>, Option<xr::Space>) {
@@ -36,16 +36,29 @@ pub fn projection_opengl_fov(fov: xr::Fovf, znear: f32) -> Matrix4<f32> {
     projection_opengl(tan_left, tan_right, tan_up, tan_down, znear)
 }
 
-pub fn view(position: xr::Vector3f, orientation: xr::Quaternionf) -> Matrix4<f32> {
-    let position: Vector3<f32> = position.into();
-    let position = Translation3::from(position);
-
-    let orientation: UnitQuaternion<f32> = orientation.into();
-
+/// Converts a pose from the stage space of the runtime to the playfield.
+/// The player faces +z of the playfield, where notes come from, and -z of the stage
+pub fn pose_to_world(pose: xr::Posef) -> Isometry3<f32> {
+    let stage_rotation = UnitQuaternion::from_euler_angles(0.0, std::f32::consts::PI, 0.0);
+    let position: Vector3<f32> = pose.position.into();
+    let orientation: UnitQuaternion<f32> = pose.orientation.into();
     Isometry3::from_parts(
-        position,
-        UnitQuaternion::from_euler_angles(0.0, 3.14, 0.0) * orientation,
+        Translation3::from(stage_rotation * position),
+        stage_rotation * orientation,
     )
+}
+
+/// View matrix of an eye, `camera` moves the whole stage in the playfield
+pub fn view(
+    camera: &Isometry3<f32>,
+    position: xr::Vector3f,
+    orientation: xr::Quaternionf,
+) -> Matrix4<f32> {
+    (camera
+        * pose_to_world(xr::Posef {
+            position,
+            orientation,
+        }))
     .inverse()
     .to_homogeneous()
 }
@@ -0,0 +1,89 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum ParseErrorKind {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    // An object that does not match the expected schema, it gets skipped
+    InvalidObject(serde_json::Error),
+    MissingField,
+    UnknownValue(i64),
+    OutOfRange(i64),
+    // Beats can't be turned into time without a positive tempo
+    InvalidBpm(f32),
+    UnknownDifficulty(String),
+}
+
+/// A problem found while reading a map. `json_path` points at the offending value,
+/// `index` is the position of the object inside its array, if there is one
+#[derive(Debug)]
+pub struct ParseError {
+    pub file: PathBuf,
+    pub json_path: String,
+    pub index: Option<usize>,
+    pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+    pub fn new(file: &Path, json_path: String, kind: ParseErrorKind) -> Self {
+        Self {
+            file: file.to_path_buf(),
+            json_path,
+            index: None,
+            kind,
+        }
+    }
+    pub fn at_index(mut self, index: usize) -> Self {
+        self.index = Some(index);
+        self
+    }
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseErrorKind::Io(e) => write!(f, "{}", e),
+            ParseErrorKind::Json(e) => write!(f, "invalid JSON: {}", e),
+            ParseErrorKind::InvalidObject(e) => write!(f, "invalid object: {}", e),
+            ParseErrorKind::MissingField => write!(f, "missing field"),
+            ParseErrorKind::UnknownValue(value) => write!(f, "unknown value {}", value),
+            ParseErrorKind::OutOfRange(value) => write!(f, "value {} is out of range", value),
+            ParseErrorKind::InvalidBpm(bpm) => write!(f, "tempo {} bpm is not positive", bpm),
+            ParseErrorKind::UnknownDifficulty(difficulty) => {
+                write!(f, "difficulty {} is not listed", difficulty)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.file.display())?;
+        if !self.json_path.is_empty() {
+            write!(f, " at {}", self.json_path)?;
+        }
+        if let Some(index) = self.index {
+            write!(f, " (object #{})", index)?;
+        }
+        write!(f, ": {}", self.kind)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Recoverable problems collected while parsing. Objects mentioned here were skipped
+/// or replaced with a fallback value
+#[derive(Debug, Default)]
+pub struct ParseReport {
+    pub warnings: Vec<ParseError>,
+}
+
+impl ParseReport {
+    pub fn warn(&mut self, warning: ParseError) {
+        self.warnings.push(warning);
+    }
+    pub fn is_clean(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
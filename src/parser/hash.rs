@@ -0,0 +1,208 @@
+use super::*;
+
+/// Identifies a map like BeatSaver does: SHA-1 of the info file followed by every
+/// difficulty file in the order the info file lists them. Renaming the song folder
+/// keeps the same hash
+pub fn map_hash(info: &SongInfo) -> Result<String, ParseError> {
+    let read = |path: &Path| {
+        std::fs::read(path).map_err(|e| ParseError::new(path, String::new(), ParseErrorKind::Io(e)))
+    };
+    let info_bytes = read(&info.path)?;
+    // `SongInfo` sorts difficulties by rank, so the order comes from the file itself
+    let level: serde_json::Value = serde_json::from_slice(&info_bytes)
+        .map_err(|e| ParseError::new(&info.path, String::new(), ParseErrorKind::Json(e)))?;
+    let files: Vec<&str> = match level.get("_difficultyBeatmapSets") {
+        Some(sets) => sets
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|set| set.get("_difficultyBeatmaps")?.as_array())
+            .flatten()
+            .filter_map(|beatmap| beatmap.get("_beatmapFilename")?.as_str())
+            .collect(),
+        None => level
+            .get("difficultyLevels")
+            .and_then(|levels| levels.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|level| level.get("jsonPath")?.as_str())
+            .collect(),
+    };
+
+    let dir = info.path.parent().unwrap_or_else(|| Path::new("."));
+    let mut sha1 = Sha1::new();
+    sha1.update(&info_bytes);
+    for file in files {
+        sha1.update(&read(&dir.join(file))?);
+    }
+    Ok(sha1
+        .finish()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// SHA-1 digest of data that is fed in pieces
+pub struct Sha1 {
+    state: [u32; 5],
+    // Bytes that don't fill a whole block yet
+    block: [u8; 64],
+    block_len: usize,
+    // Total length in bytes
+    length: u64,
+}
+
+impl Sha1 {
+    pub fn new() -> Self {
+        Self {
+            state: [
+                0x6745_2301,
+                0xefcd_ab89,
+                0x98ba_dcfe,
+                0x1032_5476,
+                0xc3d2_e1f0,
+            ],
+            block: [0; 64],
+            block_len: 0,
+            length: 0,
+        }
+    }
+
+    pub fn update(&mut self, mut bytes: &[u8]) {
+        self.length += bytes.len() as u64;
+        while !bytes.is_empty() {
+            let taken = (64 - self.block_len).min(bytes.len());
+            self.block[self.block_len..self.block_len + taken].copy_from_slice(&bytes[..taken]);
+            self.block_len += taken;
+            bytes = &bytes[taken..];
+            if self.block_len == 64 {
+                let block = self.block;
+                self.process(&block);
+                self.block_len = 0;
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> [u8; 20] {
+        let bit_length = self.length.wrapping_mul(8);
+        // A one bit, zeros up to 8 bytes before the end of a block, then the length
+        self.update(&[0x80]);
+        while self.block_len != 56 {
+            self.update(&[0]);
+        }
+        self.update(&bit_length.to_be_bytes());
+        let mut digest = [0; 20];
+        for (bytes, word) in digest.chunks_mut(4).zip(self.state.iter()) {
+            bytes.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+
+    fn process(&mut self, block: &[u8; 64]) {
+        let mut words = [0u32; 80];
+        for (word, bytes) in words.iter_mut().zip(block.chunks(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for i in 16..80 {
+            words[i] = (words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = self.state;
+        for (i, word) in words.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5a82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ed9_eba1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1b_bcdc),
+                _ => (b ^ c ^ d, 0xca62_c1d6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (state, value) in self.state.iter_mut().zip([a, b, c, d, e].iter()) {
+            *state = state.wrapping_add(*value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const FIPS_448_BITS: &[u8] = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+
+    // Digest of `pieces` fed one after the other
+    fn hex(pieces: &[&[u8]]) -> String {
+        let mut sha1 = Sha1::new();
+        for piece in pieces {
+            sha1.update(piece);
+        }
+        sha1.finish()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    fn fixture() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/Lopsided")
+    }
+
+    // FIPS 180-2 appendix A
+    #[test]
+    fn known_answers() {
+        assert_eq!(hex(&[b""]), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(hex(&[b"abc"]), "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(
+            hex(&[FIPS_448_BITS]),
+            "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+        );
+        assert_eq!(
+            hex(&[&[b'a'; 1_000_000]]),
+            "34aa973cd4c4daa4f61eeb2bdbad27316534016f"
+        );
+    }
+
+    #[test]
+    fn pieces_hash_like_the_whole() {
+        let message = [FIPS_448_BITS, FIPS_448_BITS].concat();
+        let whole = hex(&[&message]);
+        // Around the end of the first block and the start of the length
+        for &split in &[1, 55, 56, 63, 64, 65, 111] {
+            assert_eq!(hex(&[&message[..split], &message[split..]]), whole);
+        }
+    }
+
+    #[test]
+    fn map_hash_covers_info_and_difficulties_in_order() {
+        let dir = fixture();
+        let info_bytes = std::fs::read(dir.join("Info.dat")).unwrap();
+        let difficulty = std::fs::read(dir.join("Expert.dat")).unwrap();
+        // Both characteristics list the same file, it's hashed once for each
+        let expected = hex(&[&info_bytes, &difficulty, &difficulty]);
+        let info = info::open_info(&dir).unwrap();
+        assert_eq!(map_hash(&info).unwrap(), expected);
+    }
+
+    #[test]
+    fn renamed_folders_keep_the_hash() {
+        let renamed = std::env::temp_dir().join("slashmania-hash-renamed");
+        std::fs::create_dir_all(&renamed).unwrap();
+        for file in &["Info.dat", "Expert.dat"] {
+            std::fs::copy(fixture().join(file), renamed.join(file)).unwrap();
+        }
+        let renamed_hash = map_hash(&info::open_info(&renamed).unwrap()).unwrap();
+        std::fs::remove_dir_all(&renamed).unwrap();
+        assert_eq!(
+            renamed_hash,
+            map_hash(&info::open_info(&fixture()).unwrap()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use super::model::{ColorSchemeOverrideV2, ColorV2, DifficultyBeatmapV2, InfoV2, LegacyInfo};
+use super::*;
+use crate::colors::{Color, ColorOverrides};
+use std::path::PathBuf;
+
+pub struct DifficultyBeatmap {
+    pub difficulty: String,
+    pub rank: i32,
+    pub file: String,
+    pub note_jump_speed: f32,
+    pub note_jump_offset: f32,
+    // Only legacy info.json can give every difficulty its own audio
+    pub song_file: Option<String>,
+    pub colors: ColorOverrides,
+}
+
+pub struct DifficultyBeatmapSet {
+    pub characteristic: String,
+    pub difficulties: Vec<DifficultyBeatmap>,
+}
+
+pub struct SongInfo {
+    pub path: PathBuf,
+    pub song_name: String,
+    pub song_sub_name: String,
+    pub song_author_name: String,
+    pub level_author_name: String,
+    pub bpm: f32,
+    pub song_file: String,
+    pub song_time_offset: f32,
+    pub preview_start_time: f32,
+    pub cover_image_file: Option<String>,
+    pub difficulty_sets: Vec<DifficultyBeatmapSet>,
+}
+
+impl SongInfo {
+    pub fn find_beatmap(
+        &self,
+        characteristic: &str,
+        difficulty: &str,
+    ) -> Option<&DifficultyBeatmap> {
+        self.difficulty_sets
+            .iter()
+            .filter(|set| set.characteristic.eq_ignore_ascii_case(characteristic))
+            .flat_map(|set| set.difficulties.iter())
+            .find(|beatmap| beatmap.difficulty.eq_ignore_ascii_case(difficulty))
+    }
+    /// Characteristic of the set `beatmap` belongs to, spelled like the info file does
+    pub fn characteristic_of(&self, beatmap: &DifficultyBeatmap) -> Option<&str> {
+        self.difficulty_sets
+            .iter()
+            .find(|set| {
+                set.difficulties
+                    .iter()
+                    .any(|other| std::ptr::eq(other, beatmap))
+            })
+            .map(|set| set.characteristic.as_str())
+    }
+    /// 360 and 90 degree maps turn the lane with rotation events
+    pub fn turns_lane(&self, beatmap: &DifficultyBeatmap) -> bool {
+        matches!(
+            self.characteristic_of(beatmap),
+            Some("360Degree") | Some("90Degree")
+        )
+    }
+    pub fn beatmap_song_file<'a>(&'a self, beatmap: &'a DifficultyBeatmap) -> &'a str {
+        beatmap.song_file.as_ref().unwrap_or(&self.song_file)
+    }
+}
+
+/// Reads `Info.dat` of the song folder, falling back to the legacy `info.json`
+pub fn open_info(dir: &Path) -> Result<SongInfo, ParseError> {
+    for name in &["Info.dat", "info.dat"] {
+        let path = dir.join(name);
+        if path.is_file() {
+            let info: InfoV2 = read_json(&path)?;
+            return Ok(from_v2(path, info));
+        }
+    }
+    let path = dir.join("info.json");
+    let info: LegacyInfo = read_json(&path)?;
+    from_legacy(path, info)
+}
+
+fn color(color: &Option<ColorV2>) -> Option<Color> {
+    color.as_ref().map(|color| [color.r, color.g, color.b])
+}
+
+/// Colours SongCore gives the difficulty win over the colour scheme it picks
+fn beatmap_colors(
+    beatmap: &DifficultyBeatmapV2,
+    color_schemes: &[ColorSchemeOverrideV2],
+) -> ColorOverrides {
+    let scheme = beatmap
+        .color_scheme_index
+        .and_then(|index| color_schemes.get(index))
+        .filter(|scheme| scheme.use_override)
+        .map(|scheme| {
+            let scheme = &scheme.color_scheme;
+            ColorOverrides {
+                notes: [color(&scheme.saber_a_color), color(&scheme.saber_b_color)],
+                lights: [
+                    color(&scheme.environment_color_0),
+                    color(&scheme.environment_color_1),
+                ],
+                obstacles: color(&scheme.obstacles_color),
+            }
+        })
+        .unwrap_or_default();
+    let custom = beatmap
+        .custom_data
+        .as_ref()
+        .map(|custom| ColorOverrides {
+            notes: [color(&custom.color_left), color(&custom.color_right)],
+            lights: [color(&custom.env_color_left), color(&custom.env_color_right)],
+            obstacles: color(&custom.obstacle_color),
+        })
+        .unwrap_or_default();
+    custom.or(&scheme)
+}
+
+fn from_v2(path: PathBuf, info: InfoV2) -> SongInfo {
+    let color_schemes = &info.color_schemes;
+    let difficulty_sets = info
+        .difficulty_beatmap_sets
+        .into_iter()
+        .map(|set| {
+            let mut difficulties: Vec<DifficultyBeatmap> = set
+                .difficulty_beatmaps
+                .into_iter()
+                .map(|beatmap| DifficultyBeatmap {
+                    colors: beatmap_colors(&beatmap, color_schemes),
+                    difficulty: beatmap.difficulty,
+                    rank: beatmap.difficulty_rank,
+                    file: beatmap.beatmap_filename,
+                    note_jump_speed: beatmap.note_jump_movement_speed,
+                    note_jump_offset: beatmap.note_jump_start_beat_offset,
+                    song_file: None,
+                })
+                .collect();
+            difficulties.sort_by_key(|beatmap| beatmap.rank);
+            DifficultyBeatmapSet {
+                characteristic: set.beatmap_characteristic_name,
+                difficulties,
+            }
+        })
+        .collect();
+    SongInfo {
+        path,
+        song_name: info.song_name,
+        song_sub_name: info.song_sub_name,
+        song_author_name: info.song_author_name,
+        level_author_name: info.level_author_name,
+        bpm: info.beats_per_minute,
+        song_file: info.song_filename,
+        song_time_offset: info.song_time_offset,
+        preview_start_time: info.preview_start_time,
+        cover_image_file: info.cover_image_filename,
+        difficulty_sets,
+    }
+}
+
+fn from_legacy(path: PathBuf, info: LegacyInfo) -> Result<SongInfo, ParseError> {
+    let song_file = match info.difficulty_levels.first() {
+        Some(level) => level.audio_path.clone(),
+        None => {
+            return Err(ParseError::new(
+                &path,
+                "difficultyLevels".to_string(),
+                ParseErrorKind::MissingField,
+            ))
+        }
+    };
+    let difficulties = info
+        .difficulty_levels
+        .into_iter()
+        .map(|level| DifficultyBeatmap {
+            difficulty: level.difficulty,
+            rank: level.difficulty_rank,
+            file: level.json_path,
+            note_jump_speed: 0.0,
+            note_jump_offset: 0.0,
+            song_file: Some(level.audio_path),
+            colors: Default::default(),
+        })
+        .collect();
+    Ok(SongInfo {
+        path,
+        song_name: info.song_name,
+        song_sub_name: info.song_sub_name,
+        song_author_name: info.author_name,
+        level_author_name: String::new(),
+        bpm: info.beats_per_minute,
+        song_file,
+        song_time_offset: 0.0,
+        preview_start_time: info.preview_start_time,
+        cover_image_file: info.cover_image_path,
+        difficulty_sets: vec![DifficultyBeatmapSet {
+            characteristic: "Standard".to_string(),
+            difficulties,
+        }],
+    })
+}
@@ -0,0 +1,300 @@
+pub mod error;
+mod hash;
+pub mod info;
+pub mod model;
+mod v2;
+mod v3;
+
+pub use error::{ParseError, ParseErrorKind, ParseReport};
+pub use hash::map_hash;
+pub use info::{DifficultyBeatmap, SongInfo};
+
+pub struct ParsedSong {
+    pub notes: Vec<Note>,
+    pub obstacles: Vec<Obstacle>,
+    // Only v3 maps have arcs
+    pub arcs: Vec<Arc>,
+    pub light_events: Vec<LightEvent>,
+    // Empty unless the characteristic turns the lane
+    pub lane_rotations: LaneRotations,
+    // Colours the map replaces in the colour scheme of the player
+    pub colors: ColorOverrides,
+    pub bpm: f32,
+    pub tempo_map: TempoMap,
+    pub song_file: String,
+    pub note_jump_speed: f32,
+    pub note_jump_offset: f32,
+    pub report: ParseReport,
+}
+
+use crate::colors::ColorOverrides;
+use crate::components::{arc::Arc, note::*, obstacle::*};
+use crate::lanes::{LaneRotations, RotationEvent};
+use crate::lighting::{LightEvent, LightEventKind};
+use crate::scoring::ScoringType;
+use crate::timing::TempoMap;
+use serde::de::DeserializeOwned;
+use std::fs::File;
+use std::io::BufReader;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+#[derive(Debug, PartialEq)]
+pub enum FormatVersion {
+    V2,
+    V3,
+}
+
+pub fn detect_version(level: &serde_json::Value) -> FormatVersion {
+    if let Some(version) = level.get("version").and_then(|version| version.as_str()) {
+        if version.starts_with('3') {
+            return FormatVersion::V3;
+        }
+    }
+    if level.get("colorNotes").is_some() {
+        FormatVersion::V3
+    } else {
+        FormatVersion::V2
+    }
+}
+
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, ParseError> {
+    let file = File::open(path)
+        .map_err(|e| ParseError::new(path, String::new(), ParseErrorKind::Io(e)))?;
+    serde_json::from_reader(BufReader::new(file))
+        .map_err(|e| ParseError::new(path, String::new(), ParseErrorKind::Json(e)))
+}
+
+/// Deserializes a single object of a map array. Broken objects are reported and skipped
+pub fn read_object<T: DeserializeOwned>(
+    path: &Path,
+    array: &str,
+    index: usize,
+    value: serde_json::Value,
+    report: &mut ParseReport,
+) -> Option<T> {
+    match serde_json::from_value(value) {
+        Ok(object) => Some(object),
+        Err(e) => {
+            report.warn(
+                ParseError::new(
+                    path,
+                    format!("{}[{}]", array, index),
+                    ParseErrorKind::InvalidObject(e),
+                )
+                .at_index(index),
+            );
+            None
+        }
+    }
+}
+
+pub fn object_warning(
+    path: &Path,
+    array: &str,
+    index: usize,
+    field: &str,
+    kind: ParseErrorKind,
+) -> ParseError {
+    ParseError::new(path, format!("{}[{}].{}", array, index, field), kind).at_index(index)
+}
+
+/// Warns about a value outside of `range`, the object it belongs to gets skipped
+pub fn check_range(
+    path: &Path,
+    array: &str,
+    index: usize,
+    (field, value): (&str, i64),
+    range: RangeInclusive<i64>,
+    report: &mut ParseReport,
+) -> bool {
+    if !range.contains(&value) {
+        report.warn(object_warning(
+            path,
+            array,
+            index,
+            field,
+            ParseErrorKind::OutOfRange(value),
+        ));
+        return false;
+    }
+    true
+}
+
+/// Notes outside of the standard 4x3 grid come from mapping extensions we don't support
+pub fn check_grid(
+    path: &Path,
+    array: &str,
+    index: usize,
+    line_index: (&str, i64),
+    line_layer: (&str, i64),
+    report: &mut ParseReport,
+) -> bool {
+    check_range(path, array, index, line_index, 0..=3, report)
+        && check_range(path, array, index, line_layer, 0..=2, report)
+}
+
+pub fn note_type_from_raw(note_type: i64) -> Option<NoteType> {
+    match note_type {
+        0 => Some(NoteType::Red),
+        1 => Some(NoteType::Blue),
+        3 => Some(NoteType::Mine),
+        _ => None,
+    }
+}
+
+pub fn direction_from_raw(direction: i64) -> Option<Direction> {
+    match direction {
+        0 => Some(Direction::Top),
+        1 => Some(Direction::Bottom),
+        2 => Some(Direction::Left),
+        3 => Some(Direction::Right),
+
+        4 => Some(Direction::TopLeft),
+        5 => Some(Direction::TopRight),
+
+        6 => Some(Direction::BottomLeft),
+        7 => Some(Direction::BottomRight),
+
+        8 => Some(Direction::NoDirection),
+        _ => None,
+    }
+}
+
+/// Parses one difficulty of the song described by `info`
+pub fn open_file(
+    path: &Path,
+    info: &SongInfo,
+    beatmap: &DifficultyBeatmap,
+) -> Result<ParsedSong, ParseError> {
+    let mut report = ParseReport::default();
+
+    let level: serde_json::Value = read_json(path)?;
+
+    let version = detect_version(&level);
+    let level_bpm = match version {
+        // Maps made for info.json carry their own tempo
+        FormatVersion::V2 => level.get("_beatsPerMinute").and_then(|bpm| bpm.as_f64()),
+        FormatVersion::V3 => None,
+    };
+    let bpm = level_bpm.map(|bpm| bpm as f32).unwrap_or(info.bpm);
+    if !crate::timing::is_valid_bpm(bpm) {
+        let file = if level_bpm.is_some() { path } else { &info.path };
+        return Err(ParseError::new(
+            file,
+            "_beatsPerMinute".to_string(),
+            ParseErrorKind::InvalidBpm(bpm),
+        ));
+    }
+
+    let (tempo_map, mut notes, mut obstacles, mut arcs, mut light_events, mut rotations) = match version {
+        FormatVersion::V2 => {
+            let level: model::DifficultyV2 = serde_json::from_value(level)
+                .map_err(|e| ParseError::new(path, String::new(), ParseErrorKind::Json(e)))?;
+            let tempo_map = TempoMap::new(bpm, &v2::read_bpm_changes(path, &level, &mut report));
+            let light_events = v2::read_light_events(path, &level, &tempo_map, &mut report);
+            let rotations = v2::read_rotation_events(path, &level, &tempo_map, &mut report);
+            let (notes, obstacles) = v2::read_objects(path, level, &tempo_map, &mut report);
+            (tempo_map, notes, obstacles, vec![], light_events, rotations)
+        }
+        FormatVersion::V3 => {
+            let level: model::DifficultyV3 = serde_json::from_value(level)
+                .map_err(|e| ParseError::new(path, String::new(), ParseErrorKind::Json(e)))?;
+            let tempo_map = TempoMap::new(bpm, &v3::read_bpm_changes(path, &level, &mut report));
+            let light_events = v3::read_light_events(path, &level, &tempo_map, &mut report);
+            let rotations = v3::read_rotation_events(path, &level, &tempo_map, &mut report);
+            let (notes, obstacles, arcs) =
+                v3::read_objects(path, level, &tempo_map, &mut report);
+            (tempo_map, notes, obstacles, arcs, light_events, rotations)
+        }
+    };
+    // Shifts the whole map against the audio
+    let offset = info.song_time_offset * 1000.0;
+    if offset != 0.0 {
+        notes.iter_mut().for_each(|note| note.time += offset);
+        obstacles
+            .iter_mut()
+            .for_each(|obstacle| obstacle.time += offset);
+        arcs.iter_mut().for_each(|arc| {
+            arc.time += offset;
+            arc.tail_time += offset;
+        });
+        light_events
+            .iter_mut()
+            .for_each(|event| event.time += offset);
+        rotations
+            .iter_mut()
+            .for_each(|event| event.time += offset);
+    }
+    // Standard maps sometimes carry rotation events the game ignores for them
+    let lane_rotations = if info.turns_lane(beatmap) {
+        LaneRotations::new(rotations)
+    } else {
+        LaneRotations::default()
+    };
+    lane_rotations.apply(&mut notes, &mut obstacles, &mut arcs);
+    Ok(ParsedSong {
+        notes,
+        obstacles,
+        arcs,
+        light_events,
+        lane_rotations,
+        colors: beatmap.colors,
+        bpm,
+        tempo_map,
+        song_file: info.beatmap_song_file(beatmap).to_string(),
+        note_jump_speed: if beatmap.note_jump_speed > 0.0 {
+            beatmap.note_jump_speed
+        } else {
+            crate::timing::default_note_jump_speed(&beatmap.difficulty)
+        },
+        note_jump_offset: beatmap.note_jump_offset,
+        report,
+    })
+}
+
+/// Finds the requested difficulty in the song folder and parses it
+pub fn open_song(
+    dir: &Path,
+    characteristic: &str,
+    difficulty: &str,
+) -> Result<(SongInfo, ParsedSong), ParseError> {
+    let info = info::open_info(dir)?;
+    let parsed_song = {
+        let beatmap = info
+            .find_beatmap(characteristic, difficulty)
+            .ok_or_else(|| {
+                ParseError::new(
+                    &info.path,
+                    "_difficultyBeatmapSets".to_string(),
+                    ParseErrorKind::UnknownDifficulty(format!("{} {}", characteristic, difficulty)),
+                )
+            })?;
+        open_file(&dir.join(&beatmap.file), &info, beatmap)?
+    };
+    Ok((info, parsed_song))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_without_a_usable_tempo_are_rejected() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/Lopsided");
+        let mut info = info::open_info(&dir).unwrap();
+        info.bpm = 0.0;
+        let beatmap = info.find_beatmap("Standard", "Expert").unwrap();
+        match open_file(&dir.join("Expert.dat"), &info, beatmap) {
+            Err(ParseError {
+                kind: ParseErrorKind::InvalidBpm(bpm),
+                file,
+                ..
+            }) => {
+                assert_eq!(bpm, 0.0);
+                assert_eq!(file, info.path);
+            }
+            other => panic!("expected an invalid tempo, got {:?}", other.map(|_| ())),
+        }
+    }
+}
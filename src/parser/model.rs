@@ -0,0 +1,302 @@
+use serde::Deserialize;
+
+// Objects are kept as raw values here and deserialized one by one,
+// so a single broken note does not take the whole map with it
+
+#[derive(Deserialize)]
+pub struct DifficultyV2 {
+    #[serde(rename = "_notes", default)]
+    pub notes: Vec<serde_json::Value>,
+    #[serde(rename = "_obstacles", default)]
+    pub obstacles: Vec<serde_json::Value>,
+    #[serde(rename = "_events", default)]
+    pub events: Vec<serde_json::Value>,
+    #[serde(rename = "_BPMChanges", default)]
+    pub bpm_changes: Vec<serde_json::Value>,
+    #[serde(rename = "_customData")]
+    pub custom_data: Option<CustomDataV2>,
+}
+
+#[derive(Deserialize)]
+pub struct CustomDataV2 {
+    #[serde(rename = "_BPMChanges", default)]
+    pub bpm_changes: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+pub struct BpmChangeV2 {
+    #[serde(rename = "_time")]
+    pub time: f32,
+    #[serde(rename = "_BPM")]
+    pub bpm: f32,
+}
+
+#[derive(Deserialize)]
+pub struct EventV2 {
+    #[serde(rename = "_time")]
+    pub time: f32,
+    #[serde(rename = "_value", default)]
+    pub value: i64,
+    #[serde(rename = "_floatValue")]
+    pub float_value: Option<f32>,
+}
+
+#[derive(Deserialize)]
+pub struct NoteV2 {
+    #[serde(rename = "_time")]
+    pub time: f32,
+    #[serde(rename = "_lineIndex")]
+    pub line_index: i64,
+    #[serde(rename = "_lineLayer")]
+    pub line_layer: i64,
+    #[serde(rename = "_type")]
+    pub note_type: i64,
+    #[serde(rename = "_cutDirection")]
+    pub cut_direction: i64,
+}
+
+#[derive(Deserialize)]
+pub struct ObstacleV2 {
+    #[serde(rename = "_time")]
+    pub time: f32,
+    #[serde(rename = "_lineIndex")]
+    pub line_index: i32,
+    #[serde(rename = "_type")]
+    pub obstacle_type: i64,
+    #[serde(rename = "_duration")]
+    pub duration: f32,
+    #[serde(rename = "_width")]
+    pub width: i32,
+}
+
+#[derive(Deserialize)]
+pub struct DifficultyV3 {
+    #[serde(rename = "colorNotes", default)]
+    pub color_notes: Vec<serde_json::Value>,
+    #[serde(rename = "bombNotes", default)]
+    pub bomb_notes: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub obstacles: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub sliders: Vec<serde_json::Value>,
+    #[serde(rename = "burstSliders", default)]
+    pub burst_sliders: Vec<serde_json::Value>,
+    #[serde(rename = "bpmEvents", default)]
+    pub bpm_events: Vec<serde_json::Value>,
+    #[serde(rename = "basicBeatmapEvents", default)]
+    pub basic_beatmap_events: Vec<serde_json::Value>,
+    #[serde(rename = "rotationEvents", default)]
+    pub rotation_events: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+pub struct BpmEventV3 {
+    pub b: f32,
+    pub m: f32,
+}
+
+#[derive(Deserialize)]
+pub struct BasicEventV3 {
+    pub b: f32,
+    pub et: i64,
+    #[serde(default)]
+    pub i: i64,
+    #[serde(default = "full_brightness")]
+    pub f: f32,
+}
+
+fn full_brightness() -> f32 {
+    1.0
+}
+
+#[derive(Deserialize)]
+pub struct RotationEventV3 {
+    pub b: f32,
+    #[serde(default)]
+    pub e: i64,
+    pub r: f32,
+}
+
+#[derive(Deserialize)]
+pub struct ColorNoteV3 {
+    pub b: f32,
+    pub x: i64,
+    pub y: i64,
+    pub c: i64,
+    pub d: i64,
+    #[serde(default)]
+    pub a: i32,
+}
+
+#[derive(Deserialize)]
+pub struct SliderV3 {
+    pub b: f32,
+    pub c: i64,
+    pub x: i64,
+    pub y: i64,
+    pub d: i64,
+    pub mu: f32,
+    pub tb: f32,
+    pub tx: i64,
+    pub ty: i64,
+    pub tc: i64,
+    pub tmu: f32,
+}
+
+#[derive(Deserialize)]
+pub struct BurstSliderV3 {
+    pub b: f32,
+    pub c: i64,
+    pub x: i64,
+    pub y: i64,
+    pub d: i64,
+    pub tb: f32,
+    pub tx: i64,
+    pub ty: i64,
+    pub sc: i64,
+    pub s: f32,
+}
+
+#[derive(Deserialize)]
+pub struct BombNoteV3 {
+    pub b: f32,
+    pub x: i64,
+    pub y: i64,
+}
+
+#[derive(Deserialize)]
+pub struct ObstacleV3 {
+    pub b: f32,
+    pub x: i32,
+    pub y: i32,
+    pub d: f32,
+    pub w: i32,
+    pub h: i32,
+}
+
+#[derive(Deserialize)]
+pub struct InfoV2 {
+    #[serde(rename = "_songName", default)]
+    pub song_name: String,
+    #[serde(rename = "_songSubName", default)]
+    pub song_sub_name: String,
+    #[serde(rename = "_songAuthorName", default)]
+    pub song_author_name: String,
+    #[serde(rename = "_levelAuthorName", default)]
+    pub level_author_name: String,
+    #[serde(rename = "_beatsPerMinute")]
+    pub beats_per_minute: f32,
+    #[serde(rename = "_songTimeOffset", default)]
+    pub song_time_offset: f32,
+    #[serde(rename = "_previewStartTime", default)]
+    pub preview_start_time: f32,
+    #[serde(rename = "_songFilename")]
+    pub song_filename: String,
+    #[serde(rename = "_coverImageFilename")]
+    pub cover_image_filename: Option<String>,
+    #[serde(rename = "_difficultyBeatmapSets", default)]
+    pub difficulty_beatmap_sets: Vec<DifficultyBeatmapSetV2>,
+    // Added with v3 maps, difficulties pick one by index
+    #[serde(rename = "_colorSchemes", default)]
+    pub color_schemes: Vec<ColorSchemeOverrideV2>,
+}
+
+#[derive(Deserialize)]
+pub struct ColorV2 {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+#[derive(Deserialize)]
+pub struct ColorSchemeOverrideV2 {
+    #[serde(rename = "useOverride", default)]
+    pub use_override: bool,
+    #[serde(rename = "colorScheme")]
+    pub color_scheme: ColorSchemeV2,
+}
+
+#[derive(Deserialize)]
+pub struct ColorSchemeV2 {
+    #[serde(rename = "saberAColor")]
+    pub saber_a_color: Option<ColorV2>,
+    #[serde(rename = "saberBColor")]
+    pub saber_b_color: Option<ColorV2>,
+    #[serde(rename = "environmentColor0")]
+    pub environment_color_0: Option<ColorV2>,
+    #[serde(rename = "environmentColor1")]
+    pub environment_color_1: Option<ColorV2>,
+    #[serde(rename = "obstaclesColor")]
+    pub obstacles_color: Option<ColorV2>,
+}
+
+#[derive(Deserialize)]
+pub struct DifficultyBeatmapSetV2 {
+    #[serde(rename = "_beatmapCharacteristicName")]
+    pub beatmap_characteristic_name: String,
+    #[serde(rename = "_difficultyBeatmaps", default)]
+    pub difficulty_beatmaps: Vec<DifficultyBeatmapV2>,
+}
+
+#[derive(Deserialize)]
+pub struct DifficultyBeatmapV2 {
+    #[serde(rename = "_difficulty")]
+    pub difficulty: String,
+    #[serde(rename = "_difficultyRank", default)]
+    pub difficulty_rank: i32,
+    #[serde(rename = "_beatmapFilename")]
+    pub beatmap_filename: String,
+    #[serde(rename = "_noteJumpMovementSpeed", default)]
+    pub note_jump_movement_speed: f32,
+    #[serde(rename = "_noteJumpStartBeatOffset", default)]
+    pub note_jump_start_beat_offset: f32,
+    #[serde(rename = "_beatmapColorSchemeIdx")]
+    pub color_scheme_index: Option<usize>,
+    #[serde(rename = "_customData")]
+    pub custom_data: Option<DifficultyCustomDataV2>,
+}
+
+// Colours SongCore lets a difficulty replace
+#[derive(Deserialize)]
+pub struct DifficultyCustomDataV2 {
+    #[serde(rename = "_colorLeft")]
+    pub color_left: Option<ColorV2>,
+    #[serde(rename = "_colorRight")]
+    pub color_right: Option<ColorV2>,
+    #[serde(rename = "_envColorLeft")]
+    pub env_color_left: Option<ColorV2>,
+    #[serde(rename = "_envColorRight")]
+    pub env_color_right: Option<ColorV2>,
+    #[serde(rename = "_obstacleColor")]
+    pub obstacle_color: Option<ColorV2>,
+}
+
+// info.json of the old editors, one audio file per difficulty and no characteristics
+#[derive(Deserialize)]
+pub struct LegacyInfo {
+    #[serde(rename = "songName", default)]
+    pub song_name: String,
+    #[serde(rename = "songSubName", default)]
+    pub song_sub_name: String,
+    #[serde(rename = "authorName", default)]
+    pub author_name: String,
+    #[serde(rename = "beatsPerMinute")]
+    pub beats_per_minute: f32,
+    #[serde(rename = "previewStartTime", default)]
+    pub preview_start_time: f32,
+    #[serde(rename = "coverImagePath")]
+    pub cover_image_path: Option<String>,
+    #[serde(rename = "difficultyLevels", default)]
+    pub difficulty_levels: Vec<LegacyDifficultyLevel>,
+}
+
+#[derive(Deserialize)]
+pub struct LegacyDifficultyLevel {
+    pub difficulty: String,
+    #[serde(rename = "difficultyRank", default)]
+    pub difficulty_rank: i32,
+    #[serde(rename = "audioPath")]
+    pub audio_path: String,
+    #[serde(rename = "jsonPath")]
+    pub json_path: String,
+}
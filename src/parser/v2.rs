@@ -0,0 +1,231 @@
+use super::model::{BpmChangeV2, DifficultyV2, EventV2, NoteV2, ObstacleV2};
+use super::*;
+use crate::timing::TempoMap;
+
+const NOTES: &str = "_notes";
+const OBSTACLES: &str = "_obstacles";
+const EVENTS: &str = "_events";
+
+/// Tempo changes come either from `_BPMChanges` of the editors or from type 100 events
+pub fn read_bpm_changes(
+    path: &Path,
+    level: &DifficultyV2,
+    report: &mut ParseReport,
+) -> Vec<(f32, f32)> {
+    let mut changes = vec![];
+    let custom_changes = level
+        .custom_data
+        .as_ref()
+        .map(|custom_data| &custom_data.bpm_changes[..])
+        .unwrap_or(&[]);
+    for (array, values) in &[
+        ("_BPMChanges", &level.bpm_changes[..]),
+        ("_customData._BPMChanges", custom_changes),
+    ] {
+        for (index, value) in values.iter().enumerate() {
+            let change: Option<BpmChangeV2> =
+                read_object(path, array, index, value.clone(), report);
+            if let Some(change) = change {
+                changes.push((change.time, change.bpm));
+            }
+        }
+    }
+    for (index, value) in level.events.iter().enumerate() {
+        if value
+            .get("_type")
+            .and_then(|event_type| event_type.as_i64())
+            != Some(100)
+        {
+            continue;
+        }
+        let event: Option<EventV2> = read_object(path, EVENTS, index, value.clone(), report);
+        if let Some(event) = event {
+            match event.float_value {
+                Some(bpm) => changes.push((event.time, bpm)),
+                None => report.warn(object_warning(
+                    path,
+                    EVENTS,
+                    index,
+                    "_floatValue",
+                    ParseErrorKind::MissingField,
+                )),
+            }
+        }
+    }
+    changes
+}
+
+/// Light show of the map, events the environment doesn't show are left out
+pub fn read_light_events(
+    path: &Path,
+    level: &DifficultyV2,
+    tempo_map: &TempoMap,
+    report: &mut ParseReport,
+) -> Vec<LightEvent> {
+    let mut events = vec![];
+    for (index, value) in level.events.iter().enumerate() {
+        let kind = match value
+            .get("_type")
+            .and_then(|event_type| event_type.as_i64())
+            .and_then(LightEventKind::from_raw)
+        {
+            Some(kind) => kind,
+            None => continue,
+        };
+        let event: Option<EventV2> = read_object(path, EVENTS, index, value.clone(), report);
+        if let Some(event) = event {
+            events.push(LightEvent {
+                time: tempo_map.beat_to_ms(event.time),
+                kind,
+                value: event.value,
+                float_value: event.float_value.unwrap_or(1.0),
+            });
+        }
+    }
+    events
+}
+
+/// Lane rotations of 360 and 90 degree maps, type 14 turns early and 15 late
+pub fn read_rotation_events(
+    path: &Path,
+    level: &DifficultyV2,
+    tempo_map: &TempoMap,
+    report: &mut ParseReport,
+) -> Vec<RotationEvent> {
+    let mut events = vec![];
+    for (index, value) in level.events.iter().enumerate() {
+        let early = match value.get("_type").and_then(|event_type| event_type.as_i64()) {
+            Some(14) => true,
+            Some(15) => false,
+            _ => continue,
+        };
+        let event: EventV2 = match read_object(path, EVENTS, index, value.clone(), report) {
+            Some(event) => event,
+            None => continue,
+        };
+        match crate::lanes::rotation_from_raw(event.value) {
+            Some(degrees) => events.push(RotationEvent {
+                time: tempo_map.beat_to_ms(event.time),
+                early,
+                degrees,
+            }),
+            None => report.warn(object_warning(
+                path,
+                EVENTS,
+                index,
+                "_value",
+                ParseErrorKind::UnknownValue(event.value),
+            )),
+        }
+    }
+    events
+}
+
+pub fn read_objects(
+    path: &Path,
+    level: DifficultyV2,
+    tempo_map: &TempoMap,
+    report: &mut ParseReport,
+) -> (Vec<Note>, Vec<Obstacle>) {
+    let mut notes = vec![];
+    let mut obstacles = vec![];
+    for (index, value) in level.notes.into_iter().enumerate() {
+        let note: NoteV2 = match read_object(path, NOTES, index, value, report) {
+            Some(note) => note,
+            None => continue,
+        };
+        let note_type = match note_type_from_raw(note.note_type) {
+            Some(note_type) => note_type,
+            None => {
+                report.warn(object_warning(
+                    path,
+                    NOTES,
+                    index,
+                    "_type",
+                    ParseErrorKind::UnknownValue(note.note_type),
+                ));
+                continue;
+            }
+        };
+        let direction = direction_from_raw(note.cut_direction).unwrap_or_else(|| {
+            report.warn(object_warning(
+                path,
+                NOTES,
+                index,
+                "_cutDirection",
+                ParseErrorKind::UnknownValue(note.cut_direction),
+            ));
+            Direction::NoDirection
+        });
+        if !check_grid(
+            path,
+            NOTES,
+            index,
+            ("_lineIndex", note.line_index),
+            ("_lineLayer", note.line_layer),
+            report,
+        ) {
+            continue;
+        }
+
+        notes.push(Note {
+            line_layer: note.line_layer as u8,
+            line_index: note.line_index as u8,
+            note_type,
+            time: tempo_map.beat_to_ms(note.time), // Time in ms
+            direction,
+            angle_offset: 0,
+            rotation: 0.0,
+            scoring: ScoringType::Normal,
+            link: None,
+        });
+    }
+
+    for (index, value) in level.obstacles.into_iter().enumerate() {
+        let obstacle: ObstacleV2 = match read_object(path, OBSTACLES, index, value, report) {
+            Some(obstacle) => obstacle,
+            None => continue,
+        };
+        // Walls have to fit the grid like notes, wider ones come from mapping extensions
+        let line_index = obstacle.line_index as i64;
+        if !check_range(path, OBSTACLES, index, ("_lineIndex", line_index), 0..=3, report)
+            || !check_range(
+                path,
+                OBSTACLES,
+                index,
+                ("_width", obstacle.width as i64),
+                1..=4 - line_index,
+                report,
+            )
+        {
+            continue;
+        }
+
+        // v2 only knows full height walls and crouch walls, v3 describes them as layer and height
+        let (line_layer, height) = match obstacle.obstacle_type {
+            0 => (0, 5),
+            1 => (2, 3),
+            unknown => {
+                report.warn(object_warning(
+                    path,
+                    OBSTACLES,
+                    index,
+                    "_type",
+                    ParseErrorKind::UnknownValue(unknown),
+                ));
+                (0, 5)
+            }
+        };
+
+        obstacles.push(Obstacle {
+            line_index: obstacle.line_index,
+            line_layer,
+            width: obstacle.width,
+            height,
+            time: tempo_map.beat_to_ms(obstacle.time),
+            duration: tempo_map.duration_ms(obstacle.time, obstacle.duration),
+            rotation: 0.0,
+        });
+    }
+    (notes, obstacles)
+}
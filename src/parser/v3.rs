@@ -0,0 +1,342 @@
+use super::model::{
+    BasicEventV3, BombNoteV3, BpmEventV3, BurstSliderV3, ColorNoteV3, DifficultyV3, ObstacleV3,
+    RotationEventV3, SliderV3,
+};
+use super::*;
+use crate::timing::TempoMap;
+use nalgebra::Vector2;
+
+const COLOR_NOTES: &str = "colorNotes";
+const BOMB_NOTES: &str = "bombNotes";
+const OBSTACLES: &str = "obstacles";
+const BPM_EVENTS: &str = "bpmEvents";
+const BASIC_EVENTS: &str = "basicBeatmapEvents";
+const ROTATION_EVENTS: &str = "rotationEvents";
+const SLIDERS: &str = "sliders";
+const BURST_SLIDERS: &str = "burstSliders";
+// Arcs and chains start and end on notes this close in time
+const SAME_TIME_MS: f32 = 1.0;
+
+pub fn read_bpm_changes(
+    path: &Path,
+    level: &DifficultyV3,
+    report: &mut ParseReport,
+) -> Vec<(f32, f32)> {
+    level
+        .bpm_events
+        .iter()
+        .enumerate()
+        .filter_map(|(index, value)| {
+            read_object(path, BPM_EVENTS, index, value.clone(), report)
+                .map(|event: BpmEventV3| (event.b, event.m))
+        })
+        .collect()
+}
+
+pub fn read_light_events(
+    path: &Path,
+    level: &DifficultyV3,
+    tempo_map: &TempoMap,
+    report: &mut ParseReport,
+) -> Vec<LightEvent> {
+    level
+        .basic_beatmap_events
+        .iter()
+        .enumerate()
+        .filter_map(|(index, value)| {
+            let event: BasicEventV3 = read_object(path, BASIC_EVENTS, index, value.clone(), report)?;
+            Some(LightEvent {
+                time: tempo_map.beat_to_ms(event.b),
+                kind: LightEventKind::from_raw(event.et)?,
+                value: event.i,
+                float_value: event.f,
+            })
+        })
+        .collect()
+}
+
+pub fn read_rotation_events(
+    path: &Path,
+    level: &DifficultyV3,
+    tempo_map: &TempoMap,
+    report: &mut ParseReport,
+) -> Vec<RotationEvent> {
+    level
+        .rotation_events
+        .iter()
+        .enumerate()
+        .filter_map(|(index, value)| {
+            let event: RotationEventV3 =
+                read_object(path, ROTATION_EVENTS, index, value.clone(), report)?;
+            Some(RotationEvent {
+                time: tempo_map.beat_to_ms(event.b),
+                early: event.e == 0,
+                degrees: event.r,
+            })
+        })
+        .collect()
+}
+
+pub fn read_objects(
+    path: &Path,
+    level: DifficultyV3,
+    tempo_map: &TempoMap,
+    report: &mut ParseReport,
+) -> (Vec<Note>, Vec<Obstacle>, Vec<Arc>) {
+    let mut notes = vec![];
+    let mut obstacles = vec![];
+    for (index, value) in level.color_notes.into_iter().enumerate() {
+        let note: ColorNoteV3 = match read_object(path, COLOR_NOTES, index, value, report) {
+            Some(note) => note,
+            None => continue,
+        };
+        let note_type = match read_color(path, COLOR_NOTES, index, note.c, report) {
+            Some(note_type) => note_type,
+            None => continue,
+        };
+        let direction = read_direction(path, COLOR_NOTES, index, "d", note.d, report);
+        if !check_grid(
+            path,
+            COLOR_NOTES,
+            index,
+            ("x", note.x),
+            ("y", note.y),
+            report,
+        ) {
+            continue;
+        }
+
+        notes.push(Note {
+            line_layer: note.y as u8,
+            line_index: note.x as u8,
+            note_type,
+            time: tempo_map.beat_to_ms(note.b),
+            direction,
+            angle_offset: note.a,
+            rotation: 0.0,
+            scoring: ScoringType::Normal,
+            link: None,
+        });
+    }
+
+    for (index, value) in level.bomb_notes.into_iter().enumerate() {
+        let bomb: BombNoteV3 = match read_object(path, BOMB_NOTES, index, value, report) {
+            Some(bomb) => bomb,
+            None => continue,
+        };
+        if !check_grid(
+            path,
+            BOMB_NOTES,
+            index,
+            ("x", bomb.x),
+            ("y", bomb.y),
+            report,
+        ) {
+            continue;
+        }
+
+        notes.push(Note {
+            line_layer: bomb.y as u8,
+            line_index: bomb.x as u8,
+            note_type: NoteType::Mine,
+            time: tempo_map.beat_to_ms(bomb.b),
+            direction: Direction::NoDirection,
+            angle_offset: 0,
+            rotation: 0.0,
+            scoring: ScoringType::Normal,
+            link: None,
+        });
+    }
+
+    for (index, value) in level.obstacles.into_iter().enumerate() {
+        let obstacle: ObstacleV3 = match read_object(path, OBSTACLES, index, value, report) {
+            Some(obstacle) => obstacle,
+            None => continue,
+        };
+        // Walls have to fit the grid like notes, but reach up to two layers above it
+        let (x, y) = (obstacle.x as i64, obstacle.y as i64);
+        if !check_range(path, OBSTACLES, index, ("x", x), 0..=3, report)
+            || !check_range(path, OBSTACLES, index, ("y", y), 0..=4, report)
+            || !check_range(path, OBSTACLES, index, ("w", obstacle.w as i64), 1..=4 - x, report)
+            || !check_range(path, OBSTACLES, index, ("h", obstacle.h as i64), 1..=5 - y, report)
+        {
+            continue;
+        }
+
+        obstacles.push(Obstacle {
+            line_index: obstacle.x,
+            line_layer: obstacle.y,
+            width: obstacle.w,
+            height: obstacle.h,
+            time: tempo_map.beat_to_ms(obstacle.b),
+            duration: tempo_map.duration_ms(obstacle.b, obstacle.d),
+            rotation: 0.0,
+        });
+    }
+
+    for (index, value) in level.burst_sliders.into_iter().enumerate() {
+        let chain: BurstSliderV3 = match read_object(path, BURST_SLIDERS, index, value, report) {
+            Some(chain) => chain,
+            None => continue,
+        };
+        let note_type = match read_color(path, BURST_SLIDERS, index, chain.c, report) {
+            Some(note_type) => note_type,
+            None => continue,
+        };
+        let direction = read_direction(path, BURST_SLIDERS, index, "d", chain.d, report);
+        if !check_grid(path, BURST_SLIDERS, index, ("x", chain.x), ("y", chain.y), report)
+            || !check_grid(path, BURST_SLIDERS, index, ("tx", chain.tx), ("ty", chain.ty), report)
+        {
+            continue;
+        }
+        // The segment count includes the head
+        if chain.sc < 2 {
+            report.warn(object_warning(
+                path,
+                BURST_SLIDERS,
+                index,
+                "sc",
+                ParseErrorKind::OutOfRange(chain.sc),
+            ));
+            continue;
+        }
+
+        let time = tempo_map.beat_to_ms(chain.b);
+        if let Some(head) = find_note(&mut notes, note_type, time, chain.x, chain.y) {
+            head.scoring = head.scoring.with_chain_head();
+        }
+        let links = crate::arcs::chain_links(
+            Vector2::new(chain.x as f32, chain.y as f32),
+            direction,
+            Vector2::new(chain.tx as f32, chain.ty as f32),
+            chain.sc as usize,
+            chain.s,
+        );
+        for (i, (position, tangent)) in links.into_iter().enumerate() {
+            let beat = chain.b + (chain.tb - chain.b) * (i + 1) as f32 / (chain.sc - 1) as f32;
+            notes.push(Note {
+                line_layer: position.y.round().max(0.0) as u8,
+                line_index: position.x.round().max(0.0) as u8,
+                note_type,
+                time: tempo_map.beat_to_ms(beat),
+                // Links can be cut from any side
+                direction: Direction::NoDirection,
+                angle_offset: 0,
+                rotation: 0.0,
+                scoring: ScoringType::ChainLink,
+                link: Some(ChainLink {
+                    x: position.x,
+                    y: position.y,
+                    angle: crate::arcs::roll_towards(tangent),
+                }),
+            });
+        }
+    }
+    // Bombs and links are stored separately in v3, the rest of the game expects one time-ordered list
+    notes.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+    let mut arcs = vec![];
+    for (index, value) in level.sliders.into_iter().enumerate() {
+        let slider: SliderV3 = match read_object(path, SLIDERS, index, value, report) {
+            Some(slider) => slider,
+            None => continue,
+        };
+        let note_type = match read_color(path, SLIDERS, index, slider.c, report) {
+            Some(note_type) => note_type,
+            None => continue,
+        };
+        if !check_grid(path, SLIDERS, index, ("x", slider.x), ("y", slider.y), report)
+            || !check_grid(path, SLIDERS, index, ("tx", slider.tx), ("ty", slider.ty), report)
+        {
+            continue;
+        }
+
+        let time = tempo_map.beat_to_ms(slider.b);
+        let tail_time = tempo_map.beat_to_ms(slider.tb);
+        if let Some(head) = find_note(&mut notes, note_type, time, slider.x, slider.y) {
+            head.scoring = head.scoring.with_arc_head();
+        }
+        if let Some(tail) = find_note(&mut notes, note_type, tail_time, slider.tx, slider.ty) {
+            tail.scoring = tail.scoring.with_arc_tail();
+        }
+        arcs.push(Arc {
+            note_type,
+            time,
+            tail_time,
+            line_index: slider.x as u8,
+            line_layer: slider.y as u8,
+            direction: read_direction(path, SLIDERS, index, "d", slider.d, report),
+            tail_line_index: slider.tx as u8,
+            tail_line_layer: slider.ty as u8,
+            tail_direction: read_direction(path, SLIDERS, index, "tc", slider.tc, report),
+            multiplier: slider.mu,
+            tail_multiplier: slider.tmu,
+            rotation: 0.0,
+        });
+    }
+    (notes, obstacles, arcs)
+}
+
+fn read_color(
+    path: &Path,
+    array: &str,
+    index: usize,
+    color: i64,
+    report: &mut ParseReport,
+) -> Option<NoteType> {
+    match color {
+        0 => Some(NoteType::Red),
+        1 => Some(NoteType::Blue),
+        unknown => {
+            report.warn(object_warning(
+                path,
+                array,
+                index,
+                "c",
+                ParseErrorKind::UnknownValue(unknown),
+            ));
+            None
+        }
+    }
+}
+
+/// Unknown cut directions are played as dots
+fn read_direction(
+    path: &Path,
+    array: &str,
+    index: usize,
+    field: &str,
+    direction: i64,
+    report: &mut ParseReport,
+) -> Direction {
+    direction_from_raw(direction).unwrap_or_else(|| {
+        report.warn(object_warning(
+            path,
+            array,
+            index,
+            field,
+            ParseErrorKind::UnknownValue(direction),
+        ));
+        Direction::NoDirection
+    })
+}
+
+/// Note or chain link an arc or chain starts or ends on
+fn find_note(
+    notes: &mut [Note],
+    note_type: NoteType,
+    time: f32,
+    x: i64,
+    y: i64,
+) -> Option<&mut Note> {
+    notes.iter_mut().find(|note| {
+        let (line_index, line_layer) = match &note.link {
+            Some(link) => (link.x, link.y),
+            None => (note.line_index as f32, note.line_layer as f32),
+        };
+        note.note_type == note_type
+            && (note.time - time).abs() < SAME_TIME_MS
+            && (line_index - x as f32).abs() < 0.01
+            && (line_layer - y as f32).abs() < 0.01
+    })
+}
@@ -9,13 +9,16 @@ use specs::Join;
 struct OrientationInfo {
     projection: [[f32; 4]; 4],
     view: [[f32; 4]; 4],
+    environment_light: [f32; 4],
 }
 
 struct DrawObjectInfo {
+    entity: specs::Entity,
     model: String,
     texture: String,
     shader: String,
     transform: [[f32; 4]; 4],
+    tint: [f32; 4],
 }
 
 impl Window {
@@ -23,6 +26,8 @@ impl Window {
         &self,
         target: &mut glium::framebuffer::SimpleFrameBuffer,
         right_eye: bool,
+        camera: &nalgebra::Isometry3<f32>,
+        environment_light: [f32; 4],
     ) -> OrientationInfo {
         let fov = {
             if right_eye {
@@ -44,9 +49,13 @@ impl Window {
         target.clear_color_and_depth((0.0, 0.0, 0.0, 1.0), 1.0);
 
         let projection: [[f32; 4]; 4] = xrmath::projection_opengl_fov(fov, 0.1).into();
-        let view: [[f32; 4]; 4] = xrmath::view(position, orientation).into();
+        let view: [[f32; 4]; 4] = xrmath::view(camera, position, orientation).into();
 
-        let frame_draw_info = OrientationInfo { projection, view };
+        let frame_draw_info = OrientationInfo {
+            projection,
+            view,
+            environment_light,
+        };
 
         frame_draw_info
     }
@@ -56,7 +65,10 @@ impl Window {
         object: DrawObjectInfo,
         target: &mut glium::framebuffer::SimpleFrameBuffer,
     ) {
-        let model = self.models.get(&object.model);
+        let model = self
+            .curves
+            .get(&object.entity)
+            .or_else(|| self.models.get(&object.model));
         let texture = self.textures.get(&object.texture);
         let shader = self.shaders.get(&object.shader);
 
@@ -65,7 +77,7 @@ impl Window {
                 model,
                 &NoIndices(PrimitiveType::TrianglesList),
                 shader,
-                &uniform! { transform: object.transform, projection: orientation.projection, view: orientation.view, tex: texture},
+                &uniform! { transform: object.transform, projection: orientation.projection, view: orientation.view, tex: texture, tint: object.tint, environment_light: orientation.environment_light},
                 &get_params()
             ).unwrap();
         };
@@ -74,13 +86,46 @@ impl Window {
 
 impl<'a> specs::System<'a> for Window {
     type SystemData = (
+        specs::Entities<'a>,
         specs::ReadStorage<'a, transform::Transform>,
         specs::ReadStorage<'a, drawable::Drawable>,
+        specs::Write<'a, crate::game::XrStatus>,
+        specs::Write<'a, input::TrackedPoses>,
+        specs::Write<'a, input::HapticEvents>,
+        specs::Read<'a, crate::render::SpectatorCamera>,
+        specs::Read<'a, lights::EnvironmentLights>,
+        specs::ReadStorage<'a, arc::Curve>,
     );
 
-    fn run(&mut self, (transforms, drawables): Self::SystemData) {
-        let texture_array = self.get_texture_array();
+    fn run(
+        &mut self,
+        (ents, transforms, drawables, mut xr_status, mut poses, mut haptics, spectator, environment, curves): Self::SystemData,
+    ) {
+        // Arc meshes are made once for each arc and dropped with it
+        self.curves.retain(|ent, _| ents.is_alive(*ent));
+        for (ent, curve) in (&ents, &curves).join() {
+            let context = &self.context;
+            self.curves.entry(ent).or_insert_with(|| {
+                let ribbon = curve.bezier.ribbon(crate::arcs::RIBBON_WIDTH);
+                crate::obj_loader::ribbon_vertex_buf(&ribbon, context)
+            });
+        }
+
+        let texture_array = if self.xr.is_running() {
+            self.get_texture_array()
+        } else {
+            None
+        };
         self.update_xr();
+        xr_status.focused = self.xr.is_focused();
+        xr_status.exiting = self.xr.should_exit();
+        if self.xr.is_running() {
+            self.xr.update_input(&mut poses);
+            self.xr.apply_haptics(&haptics.queue);
+        } else {
+            *poses = Default::default();
+        }
+        haptics.queue.clear();
         if let Some(texture_array) = texture_array {
             let mut window_frame = glium::Frame::new(self.context.clone(), (800, 800));
             let depth_texture_array = self.depth_texture_array.as_ref().unwrap();
@@ -102,22 +147,25 @@ impl<'a> specs::System<'a> for Window {
             )
             .unwrap();
 
-            let orientation_left = self.start_frame_draw(&mut left_eye_buffer, false);
-            let orientation_right = self.start_frame_draw(&mut right_eye_buffer, true);
+            let camera = spectator.offset.unwrap_or_else(nalgebra::Isometry3::identity);
+            let orientation_left = self.start_frame_draw(&mut left_eye_buffer, false, &camera, environment.ambient);
+            let orientation_right = self.start_frame_draw(&mut right_eye_buffer, true, &camera, environment.ambient);
 
             let mut buffers = [
                 (left_eye_buffer, orientation_left),
                 (right_eye_buffer, orientation_right),
             ];
             for buffer in &mut buffers {
-                for (transform, drawable) in (&transforms, &drawables).join() {
+                for (ent, transform, drawable) in (&ents, &transforms, &drawables).join() {
                     if drawable.enabled {
                         let transform_matrix = transform.transform_matrix().into();
                         let draw_object = DrawObjectInfo {
+                            entity: ent,
                             model: drawable.model.clone(),
                             texture: drawable.texture.clone(),
                             shader: drawable.shader.clone(),
                             transform: transform_matrix,
+                            tint: drawable.tint,
                         };
                         self.draw_object(&buffer.1, draw_object, &mut buffer.0);
                     }
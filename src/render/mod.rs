@@ -10,11 +10,19 @@ pub mod backend;
 mod draw;
 mod shaders;
 
+/// Places the viewer away from the player, used to watch replays
+#[derive(Default)]
+pub struct SpectatorCamera {
+    pub offset: Option<nalgebra::Isometry3<f32>>,
+}
+
 pub struct Window {
     context: Rc<glium::backend::Context>,
     xr: OpenXR,
     shaders: HashMap<String, Program>,
     models: HashMap<String, VertexBufferAny>,
+    // Meshes of the arcs that are on screen
+    curves: HashMap<specs::Entity, VertexBufferAny>,
     textures: HashMap<String, Texture2d>,
     depth_texture_array: Option<DepthTexture2dArray>,
 }
@@ -36,6 +44,7 @@ impl Window {
             depth_texture_array: None,
             shaders: HashMap::new(),
             models: HashMap::new(),
+            curves: HashMap::new(),
             textures: HashMap::new(),
         }
     }
@@ -105,6 +114,13 @@ impl Window {
             None,
         )
         .unwrap();
+        let note_shader = glium::Program::from_source(
+            &self.context,
+            SHADER_SIMPLE_VERT,
+            SHADER_NOTE_FRAG,
+            None,
+        )
+        .unwrap();
         let wall_shader = glium::Program::from_source(
             &self.context,
             SHADER_SIMPLE_VERT,
@@ -112,8 +128,17 @@ impl Window {
             None,
         )
         .unwrap();
+        let light_shader = glium::Program::from_source(
+            &self.context,
+            SHADER_SIMPLE_VERT,
+            SHADER_LIGHT_FRAG,
+            None,
+        )
+        .unwrap();
         self.shaders.insert("simple".to_string(), simple);
+        self.shaders.insert("note".to_string(), note_shader);
         self.shaders.insert("wall".to_string(), wall_shader);
+        self.shaders.insert("light".to_string(), light_shader);
         self.shaders.insert("simple2d".to_string(), simple2d);
     }
     pub fn load_default_models(&mut self) {
@@ -6,6 +6,9 @@ in vec2 v_tex_coords;
 out vec4 color;
 
 uniform sampler2D tex;
+uniform vec4 tint;
+// Colour the environment lights throw on objects
+uniform vec4 environment_light;
 
 void main() {
     vec3 u_light = vec3(0.1,0.1,0.4);
@@ -14,7 +17,38 @@ void main() {
     vec4 dark_color = vec4(0.7, 0.7, 0.7, 1.0) * vec4(texture(tex, v_tex_coords));
     vec4 regular_color = vec4(1.0, 1.0, 1.0, 1.0) * vec4(texture(tex, v_tex_coords));
 
-    color = vec4(mix(dark_color, regular_color, brightness));
+    color = vec4(mix(dark_color, regular_color, brightness)) * tint;
+    color.rgb += environment_light.rgb * environment_light.a * 0.15;
+}
+"#;
+
+// Notes and sabers, the coloured parts of the texture take the colour of the tint
+// and white parts like the arrow stay white
+pub const SHADER_NOTE_FRAG: &'static str = r#"
+#version 140
+in vec3 v_normal;
+in vec2 v_tex_coords;
+
+out vec4 color;
+
+uniform sampler2D tex;
+uniform vec4 tint;
+uniform vec4 environment_light;
+
+void main() {
+    vec3 u_light = vec3(0.1,0.1,0.4);
+    float brightness = dot(normalize(v_normal), normalize(u_light));
+
+    vec4 texel = texture(tex, v_tex_coords);
+    float value = max(texel.r, max(texel.g, texel.b));
+    float saturation = value - min(texel.r, min(texel.g, texel.b));
+    vec3 painted = mix(vec3(value), tint.rgb * value, saturation / max(value, 0.0001));
+
+    vec4 regular_color = vec4(painted, texel.a * tint.a);
+    vec4 dark_color = vec4(painted * 0.7, texel.a * tint.a);
+
+    color = mix(dark_color, regular_color, brightness);
+    color.rgb += environment_light.rgb * environment_light.a * 0.15;
 }
 "#;
 
@@ -25,8 +59,27 @@ in vec2 v_tex_coords;
 
 out vec4 color;
 
+uniform vec4 tint;
+uniform vec4 environment_light;
+
+void main() {
+    color = tint;
+    color.rgb += environment_light.rgb * environment_light.a * 0.2;
+}
+"#;
+
+// Light strips of the environment, the intensity of the light is in the alpha of the tint
+pub const SHADER_LIGHT_FRAG: &'static str = r#"
+#version 140
+in vec3 v_normal;
+in vec2 v_tex_coords;
+
+out vec4 color;
+
+uniform vec4 tint;
+
 void main() {
-    color = vec4(1.0, 0.2, 0.2, 0.7);
+    color = vec4(tint.rgb * tint.a, 1.0);
 }
 "#;
 
@@ -0,0 +1,247 @@
+use nalgebra::{Isometry3, Quaternion, Translation3, UnitQuaternion};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"SMRP";
+const VERSION: u16 = 1;
+
+// Frame flags, the first three say which poses follow the time
+const HAS_HEAD: u8 = 1;
+const HAS_LEFT: u8 = 1 << 1;
+const HAS_RIGHT: u8 = 1 << 2;
+// Gameplay ran in this frame, paused frames only move the sabers
+const SIMULATED: u8 = 1 << 3;
+
+#[derive(Clone, Copy)]
+pub struct ReplayFrame {
+    // Song time in ms
+    pub time: f32,
+    pub simulated: bool,
+    pub head: Option<Isometry3<f32>>,
+    pub hands: [Option<Isometry3<f32>>; 2],
+}
+
+/// A recorded run, enough to play the song again with the same inputs
+pub struct Replay {
+    pub song: String,
+    pub characteristic: String,
+    pub difficulty: String,
+    pub map_hash: String,
+    // Short modifier codes, as given to `Modifiers::from_list`
+    pub modifiers: String,
+    pub note_jump_speed: f32,
+    pub score: u32,
+    pub frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        for string in &[
+            &self.song,
+            &self.characteristic,
+            &self.difficulty,
+            &self.map_hash,
+            &self.modifiers,
+        ] {
+            write_string(&mut writer, string)?;
+        }
+        writer.write_all(&self.note_jump_speed.to_le_bytes())?;
+        writer.write_all(&self.score.to_le_bytes())?;
+        writer.write_all(&(self.frames.len() as u32).to_le_bytes())?;
+        for frame in &self.frames {
+            let poses = [frame.head, frame.hands[0], frame.hands[1]];
+            let mut flags = if frame.simulated { SIMULATED } else { 0 };
+            for (i, pose) in poses.iter().enumerate() {
+                if pose.is_some() {
+                    flags |= 1 << i;
+                }
+            }
+            writer.write_all(&[flags])?;
+            writer.write_all(&frame.time.to_le_bytes())?;
+            for pose in poses.iter().filter_map(|pose| pose.as_ref()) {
+                write_pose(&mut writer, pose)?;
+            }
+        }
+        writer.flush()
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("Not a replay file"));
+        }
+        let mut version = [0; 2];
+        reader.read_exact(&mut version)?;
+        if u16::from_le_bytes(version) != VERSION {
+            return Err(invalid_data("Unsupported replay version"));
+        }
+        let song = read_string(&mut reader)?;
+        let characteristic = read_string(&mut reader)?;
+        let difficulty = read_string(&mut reader)?;
+        let map_hash = read_string(&mut reader)?;
+        let modifiers = read_string(&mut reader)?;
+        let note_jump_speed = read_f32(&mut reader)?;
+        let score = read_u32(&mut reader)?;
+        // The count isn't trusted for an allocation, a broken file runs out of frames first
+        let frame_count = read_u32(&mut reader)?;
+        let mut frames = vec![];
+        for _ in 0..frame_count {
+            let mut flags = [0; 1];
+            reader.read_exact(&mut flags)?;
+            let flags = flags[0];
+            let time = read_f32(&mut reader)?;
+            let mut read_if = |flag: u8| -> io::Result<Option<Isometry3<f32>>> {
+                if flags & flag != 0 {
+                    read_pose(&mut reader).map(Some)
+                } else {
+                    Ok(None)
+                }
+            };
+            let head = read_if(HAS_HEAD)?;
+            let left = read_if(HAS_LEFT)?;
+            let right = read_if(HAS_RIGHT)?;
+            frames.push(ReplayFrame {
+                time,
+                simulated: flags & SIMULATED != 0,
+                head,
+                hands: [left, right],
+            });
+        }
+        Ok(Self {
+            song,
+            characteristic,
+            difficulty,
+            map_hash,
+            modifiers,
+            note_jump_speed,
+            score,
+            frames,
+        })
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_string(writer: &mut impl Write, string: &str) -> io::Result<()> {
+    writer.write_all(&(string.len() as u16).to_le_bytes())?;
+    writer.write_all(string.as_bytes())
+}
+
+// Poses are stored at full precision, so scoring them again gives the same result
+fn write_pose(writer: &mut impl Write, pose: &Isometry3<f32>) -> io::Result<()> {
+    let position = pose.translation.vector;
+    let rotation = pose.rotation.quaternion().coords;
+    for value in position.iter().chain(rotation.iter()) {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_f32(reader: &mut impl Read) -> io::Result<f32> {
+    read_u32(reader).map(f32::from_bits)
+}
+
+fn read_string(reader: &mut impl Read) -> io::Result<String> {
+    let mut length = [0; 2];
+    reader.read_exact(&mut length)?;
+    let mut bytes = vec![0; u16::from_le_bytes(length) as usize];
+    reader.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(|_| invalid_data("Replay text isn't UTF-8"))
+}
+
+fn read_pose(reader: &mut impl Read) -> io::Result<Isometry3<f32>> {
+    let mut values = [0.0; 7];
+    for value in values.iter_mut() {
+        *value = read_f32(reader)?;
+    }
+    // Quaternion coordinates are stored as i, j, k, w
+    let rotation = Quaternion::new(values[6], values[3], values[4], values[5]);
+    Ok(Isometry3::from_parts(
+        Translation3::new(values[0], values[1], values[2]),
+        UnitQuaternion::new_unchecked(rotation),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay(frames: Vec<ReplayFrame>) -> Replay {
+        Replay {
+            song: "Lopsided".to_string(),
+            characteristic: "Standard".to_string(),
+            difficulty: "Expert".to_string(),
+            map_hash: "0123456789abcdef0123456789abcdef01234567".to_string(),
+            modifiers: "da,mr".to_string(),
+            note_jump_speed: 16.0,
+            score: 1234,
+            frames,
+        }
+    }
+
+    #[test]
+    fn saved_replays_load_the_same() {
+        let path = std::env::temp_dir().join("slashmania-replay-round-trip.smr");
+        let pose = Isometry3::from_parts(
+            Translation3::new(0.1, 1.6, -0.2),
+            UnitQuaternion::from_euler_angles(0.3, -0.2, 0.1),
+        );
+        let frames = vec![
+            ReplayFrame {
+                time: -10.0,
+                simulated: false,
+                head: Some(pose),
+                hands: [None, None],
+            },
+            ReplayFrame {
+                time: 11.1,
+                simulated: true,
+                head: None,
+                hands: [Some(pose), Some(pose.inverse())],
+            },
+        ];
+        replay(frames.clone()).save(&path).unwrap();
+        let loaded = Replay::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.map_hash, replay(vec![]).map_hash);
+        assert_eq!((loaded.modifiers.as_str(), loaded.score), ("da,mr", 1234));
+        assert_eq!(loaded.frames.len(), frames.len());
+        for (loaded, frame) in loaded.frames.iter().zip(&frames) {
+            assert_eq!(
+                (loaded.time, loaded.simulated),
+                (frame.time, frame.simulated)
+            );
+            assert_eq!(loaded.head, frame.head);
+            assert_eq!(loaded.hands, frame.hands);
+        }
+    }
+
+    #[test]
+    fn broken_frame_counts_fail_to_load() {
+        let path = std::env::temp_dir().join("slashmania-replay-broken-count.smr");
+        replay(vec![]).save(&path).unwrap();
+        let mut bytes = std::fs::read(&path).unwrap();
+        // The frame count is the last field of an empty replay
+        let count_start = bytes.len() - 4;
+        bytes[count_start..].copy_from_slice(&u32::MAX.to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+        let error = Replay::load(&path).err().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,385 @@
+use nalgebra::Vector3;
+
+pub const MAX_PRE_SWING_SCORE: u32 = 70;
+pub const MAX_POST_SWING_SCORE: u32 = 30;
+pub const MAX_ACCURACY_SCORE: u32 = 15;
+// Every cut of a chain link gives the same score
+pub const CHAIN_LINK_SCORE: u32 = 20;
+// Highest combo multiplier, each step doubles it
+pub const MAX_MULTIPLIER: u32 = 8;
+
+// Swing in degrees needed for the full angle scores
+const PRE_SWING_ANGLE: f32 = 100.0;
+const POST_SWING_ANGLE: f32 = 60.0;
+// Cuts this far from the note centre get no accuracy points
+const ACCURACY_DISTANCE: f32 = 0.3;
+// A blade that turns less than this in a frame is resting and ends the swing
+const MIN_SWING_STEP: f32 = 0.5;
+// Post swings are cut off after half a second at 90 fps
+const MAX_FOLLOW_FRAMES: u32 = 45;
+
+/// How arcs and chains attached to a note change its cut score
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScoringType {
+    Normal,
+    // Arcs give the post swing of their head and the pre swing of their tail in full,
+    // even when the saber didn't follow the arc in between
+    ArcHead,
+    ArcTail,
+    ArcHeadArcTail,
+    // The post swing of a chain head isn't scored, its links are worth `CHAIN_LINK_SCORE`
+    ChainHead,
+    ChainHeadArcTail,
+    ChainLink,
+    ChainLinkArcHead,
+}
+
+impl ScoringType {
+    pub fn with_arc_head(self) -> Self {
+        match self {
+            ScoringType::Normal => ScoringType::ArcHead,
+            ScoringType::ArcTail => ScoringType::ArcHeadArcTail,
+            ScoringType::ChainLink => ScoringType::ChainLinkArcHead,
+            other => other,
+        }
+    }
+    pub fn with_arc_tail(self) -> Self {
+        match self {
+            ScoringType::Normal => ScoringType::ArcTail,
+            ScoringType::ArcHead => ScoringType::ArcHeadArcTail,
+            ScoringType::ChainHead => ScoringType::ChainHeadArcTail,
+            other => other,
+        }
+    }
+    pub fn with_chain_head(self) -> Self {
+        match self {
+            ScoringType::Normal => ScoringType::ChainHead,
+            ScoringType::ArcTail => ScoringType::ChainHeadArcTail,
+            other => other,
+        }
+    }
+    pub fn is_chain_link(self) -> bool {
+        self == ScoringType::ChainLink || self == ScoringType::ChainLinkArcHead
+    }
+    fn is_chain_head(self) -> bool {
+        self == ScoringType::ChainHead || self == ScoringType::ChainHeadArcTail
+    }
+    fn full_pre_swing(self) -> bool {
+        matches!(
+            self,
+            ScoringType::ArcTail | ScoringType::ArcHeadArcTail | ScoringType::ChainHeadArcTail
+        )
+    }
+    fn full_post_swing(self) -> bool {
+        self == ScoringType::ArcHead || self == ScoringType::ArcHeadArcTail
+    }
+    /// The blade is followed after the cut only if its post swing counts
+    fn follows_post_swing(self) -> bool {
+        !self.is_chain_link() && !self.is_chain_head() && !self.full_post_swing()
+    }
+    pub fn max_score(self) -> u32 {
+        if self.is_chain_link() {
+            CHAIN_LINK_SCORE
+        } else if self.is_chain_head() {
+            MAX_PRE_SWING_SCORE + MAX_ACCURACY_SCORE
+        } else {
+            MAX_PRE_SWING_SCORE + MAX_POST_SWING_SCORE + MAX_ACCURACY_SCORE
+        }
+    }
+}
+
+/// Score of perfect cuts of the notes judged so far, including the multiplier ramp up
+#[derive(Clone, Copy, Debug)]
+pub struct MaxScore {
+    pub score: u32,
+    multiplier: u32,
+    progress: u32,
+}
+
+impl Default for MaxScore {
+    fn default() -> Self {
+        Self {
+            score: 0,
+            multiplier: 1,
+            progress: 0,
+        }
+    }
+}
+
+impl MaxScore {
+    pub fn add(&mut self, scoring: ScoringType) {
+        if self.multiplier < MAX_MULTIPLIER {
+            self.progress += 1;
+            if self.progress >= self.multiplier * 2 {
+                self.multiplier *= 2;
+                self.progress = 0;
+            }
+        }
+        self.score += scoring.max_score() * self.multiplier;
+    }
+}
+
+/// Accumulates how far a blade has rotated since it started moving in one direction
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SwingTracker {
+    direction: Option<Vector3<f32>>,
+    plane: Option<Vector3<f32>>,
+    // Degrees
+    pub angle: f32,
+}
+
+impl SwingTracker {
+    /// `direction` points from the hilt to the tip of the blade. Returns false if the
+    /// blade stopped or turned around, which starts a new swing
+    pub fn update(&mut self, direction: Vector3<f32>) -> bool {
+        let direction = direction.normalize();
+        let mut continued = true;
+        if let Some(previous) = self.direction {
+            let step = previous.angle(&direction).to_degrees();
+            if step < MIN_SWING_STEP {
+                continued = self.plane.is_none();
+                self.angle = 0.0;
+                self.plane = None;
+            } else {
+                let plane = previous.cross(&direction).normalize();
+                let reversed = self
+                    .plane
+                    .map(|previous_plane| previous_plane.dot(&plane) < 0.0)
+                    .unwrap_or(false);
+                if reversed {
+                    continued = false;
+                    self.angle = step;
+                } else {
+                    self.angle += step;
+                }
+                self.plane = Some(plane);
+            }
+        }
+        self.direction = Some(direction);
+        continued
+    }
+    pub fn reset(&mut self) {
+        *self = Default::default();
+    }
+}
+
+pub fn pre_swing_score(angle: f32) -> u32 {
+    (MAX_PRE_SWING_SCORE as f32 * clamp01(angle / PRE_SWING_ANGLE)).round() as u32
+}
+
+pub fn post_swing_score(angle: f32) -> u32 {
+    (MAX_POST_SWING_SCORE as f32 * clamp01(angle / POST_SWING_ANGLE)).round() as u32
+}
+
+pub fn accuracy_score(center_distance: f32) -> u32 {
+    (MAX_ACCURACY_SCORE as f32 * (1.0 - clamp01(center_distance / ACCURACY_DISTANCE))).round()
+        as u32
+}
+
+fn clamp01(value: f32) -> f32 {
+    value.clamp(0.0, 1.0)
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CutScore {
+    pub scoring: ScoringType,
+    pub pre_swing: u32,
+    pub post_swing: u32,
+    pub accuracy: u32,
+}
+
+impl CutScore {
+    pub fn total(&self) -> u32 {
+        if self.scoring.is_chain_link() {
+            CHAIN_LINK_SCORE
+        } else {
+            self.pre_swing + self.post_swing + self.accuracy
+        }
+    }
+}
+
+/// A good cut whose post swing is still being followed
+#[derive(Clone, Copy, Debug)]
+pub struct PendingCut {
+    scoring: ScoringType,
+    pre_swing: u32,
+    accuracy: u32,
+    swing: SwingTracker,
+    frames: u32,
+}
+
+impl PendingCut {
+    /// `swing` is the swing of the blade up to the moment of the cut
+    pub fn new(scoring: ScoringType, swing: &SwingTracker, center_distance: f32) -> Self {
+        let (pre_swing, accuracy) = if scoring.is_chain_link() {
+            (0, 0)
+        } else if scoring.full_pre_swing() {
+            (MAX_PRE_SWING_SCORE, accuracy_score(center_distance))
+        } else {
+            (
+                pre_swing_score(swing.angle),
+                accuracy_score(center_distance),
+            )
+        };
+        Self {
+            scoring,
+            pre_swing,
+            accuracy,
+            swing: SwingTracker {
+                angle: 0.0,
+                ..*swing
+            },
+            frames: 0,
+        }
+    }
+    /// Follows the blade for one more frame. Returns the final score once the swing
+    /// stopped, turned around, reached the full angle or the blade was lost
+    pub fn follow(&mut self, direction: Option<Vector3<f32>>) -> Option<CutScore> {
+        if !self.scoring.follows_post_swing() {
+            return Some(self.finish(0.0));
+        }
+        let previous_angle = self.swing.angle;
+        let direction = match direction {
+            Some(direction) => direction,
+            None => return Some(self.finish(previous_angle)),
+        };
+        let continued = self.swing.update(direction);
+        self.frames += 1;
+        if !continued {
+            Some(self.finish(previous_angle))
+        } else if self.swing.angle >= POST_SWING_ANGLE || self.frames >= MAX_FOLLOW_FRAMES {
+            Some(self.finish(self.swing.angle))
+        } else {
+            None
+        }
+    }
+    fn finish(&self, post_swing_angle: f32) -> CutScore {
+        let post_swing = if self.scoring.full_post_swing() {
+            MAX_POST_SWING_SCORE
+        } else if self.scoring.follows_post_swing() {
+            post_swing_score(post_swing_angle)
+        } else {
+            0
+        };
+        CutScore {
+            scoring: self.scoring,
+            pre_swing: self.pre_swing,
+            post_swing,
+            accuracy: self.accuracy,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Blade pitch in degrees for each frame of swings recorded at 90 fps, the blade
+    // points forward at 0 and up at 90. The cut happens on the last frame of each pre swing
+    const FULL_PRE_SWING: [f32; 9] = [85.0, 83.0, 76.0, 64.0, 48.0, 30.0, 12.0, -6.0, -20.0];
+    const FULL_POST_SWING: [f32; 7] = [-32.0, -44.0, -55.0, -64.0, -72.0, -78.0, -82.0];
+    const SHORT_PRE_SWING: [f32; 5] = [30.0, 27.0, 20.0, 10.0, 0.0];
+    const SHORT_POST_SWING: [f32; 4] = [-8.0, -14.0, -18.0, -18.2];
+    const WIDE_PRE_SWING: [f32; 8] = [170.0, 150.0, 120.0, 85.0, 50.0, 15.0, -20.0, -30.0];
+    const WIDE_POST_SWING: [f32; 4] = [-50.0, -70.0, -90.0, -110.0];
+
+    fn blade(pitch: f32) -> Vector3<f32> {
+        let pitch = pitch.to_radians();
+        Vector3::new(0.0, pitch.sin(), pitch.cos())
+    }
+
+    fn swing(pitches: &[f32]) -> SwingTracker {
+        let mut swing = SwingTracker::default();
+        for &pitch in pitches {
+            swing.update(blade(pitch));
+        }
+        swing
+    }
+
+    // Returns the frame the cut was finished on and its score
+    fn follow(cut: &mut PendingCut, pitches: &[f32]) -> Option<(usize, CutScore)> {
+        pitches
+            .iter()
+            .enumerate()
+            .find_map(|(frame, &pitch)| cut.follow(Some(blade(pitch))).map(|score| (frame, score)))
+    }
+
+    #[test]
+    fn full_swing_and_centre_cut_score_70_30_15() {
+        let mut cut = PendingCut::new(ScoringType::Normal, &swing(&FULL_PRE_SWING), 0.0);
+        let (frame, score) = follow(&mut cut, &FULL_POST_SWING).unwrap();
+        assert_eq!(frame, FULL_POST_SWING.len() - 1);
+        assert_eq!(
+            (score.pre_swing, score.post_swing, score.accuracy),
+            (
+                MAX_PRE_SWING_SCORE,
+                MAX_POST_SWING_SCORE,
+                MAX_ACCURACY_SCORE
+            )
+        );
+        assert_eq!(score.total(), 115);
+    }
+
+    #[test]
+    fn under_swing_scores_part_of_the_angle() {
+        let mut cut = PendingCut::new(ScoringType::Normal, &swing(&SHORT_PRE_SWING), 0.1);
+        // The blade comes to rest on the last frame, ending the post swing at 18 degrees
+        let (frame, score) = follow(&mut cut, &SHORT_POST_SWING).unwrap();
+        assert_eq!(frame, SHORT_POST_SWING.len() - 1);
+        assert_eq!(
+            (score.pre_swing, score.post_swing, score.accuracy),
+            (21, 9, 10)
+        );
+    }
+
+    #[test]
+    fn over_swing_is_capped() {
+        let mut cut = PendingCut::new(ScoringType::Normal, &swing(&WIDE_PRE_SWING), 0.0);
+        // The full post swing angle is reached on the third frame
+        let (frame, score) = follow(&mut cut, &WIDE_POST_SWING).unwrap();
+        assert_eq!(frame, 2);
+        assert_eq!((score.pre_swing, score.post_swing), (70, 30));
+    }
+
+    #[test]
+    fn post_swing_ends_when_the_blade_turns_around() {
+        let mut cut = PendingCut::new(ScoringType::Normal, &swing(&FULL_PRE_SWING), 0.0);
+        let (frame, score) = follow(&mut cut, &[-30.0, -38.0, -30.0]).unwrap();
+        assert_eq!(frame, 2);
+        assert_eq!(score.post_swing, 9);
+    }
+
+    #[test]
+    fn post_swing_ends_when_the_blade_is_lost() {
+        let mut cut = PendingCut::new(ScoringType::Normal, &swing(&FULL_PRE_SWING), 0.0);
+        assert!(follow(&mut cut, &FULL_POST_SWING[..2]).is_none());
+        let score = cut.follow(None).unwrap();
+        assert_eq!(score.post_swing, 12);
+    }
+
+    #[test]
+    fn slow_post_swing_is_cut_off() {
+        let mut cut = PendingCut::new(ScoringType::Normal, &swing(&FULL_PRE_SWING), 0.0);
+        let slow: Vec<f32> = (1..=60).map(|frame| -20.0 - frame as f32 * 0.8).collect();
+        let (frame, score) = follow(&mut cut, &slow).unwrap();
+        assert_eq!(frame as u32 + 1, MAX_FOLLOW_FRAMES);
+        assert_eq!(score.post_swing, 18);
+    }
+
+    #[test]
+    fn arcs_and_chains_are_finished_without_following() {
+        let short = swing(&SHORT_PRE_SWING);
+        let score = PendingCut::new(ScoringType::ArcHeadArcTail, &short, 0.0)
+            .follow(None)
+            .unwrap();
+        assert_eq!(score.total(), 115);
+        let score = PendingCut::new(ScoringType::ChainHead, &short, 0.0)
+            .follow(Some(blade(-10.0)))
+            .unwrap();
+        assert_eq!((score.pre_swing, score.post_swing), (21, 0));
+        let score = PendingCut::new(ScoringType::ChainLink, &short, 0.3)
+            .follow(None)
+            .unwrap();
+        assert_eq!(score.total(), CHAIN_LINK_SCORE);
+    }
+}
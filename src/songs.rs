@@ -1,137 +1,370 @@
 use crate::components::note::*;
 use crate::components::*;
-use nalgebra::UnitQuaternion;
-use specs::Builder;
-
-pub fn place_note(world: &mut specs::World, note: note::Note) {
-    let note_texture = match note.note_type {
-        NoteType::Red => "note_red",
-        NoteType::Blue => "note_blue",
-        NoteType::Mine => "mine",
-    }
-    .to_string();
+use nalgebra::{UnitQuaternion, Vector2};
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
 
-    let note_model = match note.note_type {
-        NoteType::Mine => "mine",
-        _ => "block",
+use crate::autoplay::AutoplayPlan;
+use crate::colors::ColorScheme;
+use crate::lanes::lane_rotation;
+use crate::modifiers::Modifiers;
+use crate::timing::NoteJump;
+
+/// Rotation of a note, its arrow points along +y
+pub fn note_rotation(note: &note::Note) -> UnitQuaternion<f32> {
+    if let Some(link) = &note.link {
+        return UnitQuaternion::from_euler_angles(0.0, 0.0, link.angle.to_radians());
     }
-    .to_string();
+    let roll = match note.direction {
+        Direction::Bottom => PI,
+        Direction::Top => 0.0,
 
-    let note_direction = match note.direction {
-        Direction::Bottom => UnitQuaternion::from_euler_angles(0.0, 0.0, 3.14),
-        Direction::Top => UnitQuaternion::from_euler_angles(0.0, 0.0, 0.0),
+        Direction::Right => FRAC_PI_2,
+        Direction::Left => -FRAC_PI_2,
+
+        Direction::BottomRight => FRAC_PI_4 * 3.0,
+        Direction::BottomLeft => -FRAC_PI_4 * 3.0,
+
+        Direction::TopRight => FRAC_PI_4,
+        Direction::TopLeft => -FRAC_PI_4,
+
+        // Dots only turn by their angle offset
+        Direction::NoDirection => 0.0,
+    };
+    // Positive roll turns arrows clockwise for the player, angle offsets are counter-clockwise
+    UnitQuaternion::from_euler_angles(0.0, 0.0, roll - (note.angle_offset as f32).to_radians())
+}
 
-        Direction::Right => UnitQuaternion::from_euler_angles(0.0, 0.0, 3.14 / 2.0),
-        Direction::Left => UnitQuaternion::from_euler_angles(0.0, 0.0, -3.14 / 2.0),
+/// Centre of a note across the playfield
+pub fn note_position(note: &note::Note) -> Vector2<f32> {
+    match &note.link {
+        Some(link) => grid_position(link.x, link.y),
+        None => grid_position(note.line_index as f32, note.line_layer as f32),
+    }
+}
 
-        Direction::BottomRight => UnitQuaternion::from_euler_angles(0.0, 0.0, 3.14 / 4.0 * 3.0),
-        Direction::BottomLeft => UnitQuaternion::from_euler_angles(0.0, 0.0, -3.14 / 4.0 * 3.0),
+/// Centre of a grid cell across the playfield, chain links and arcs also sit between cells
+pub fn grid_position(line_index: f32, line_layer: f32) -> Vector2<f32> {
+    Vector2::new(-(line_index * 0.7) + 1.0, line_layer * 0.6 + 1.0)
+}
 
-        Direction::TopRight => UnitQuaternion::from_euler_angles(0.0, 0.0, 3.14 / 4.0),
-        Direction::TopLeft => UnitQuaternion::from_euler_angles(0.0, 0.0, -3.14 / 4.0),
+pub fn note_components(
+    note: &note::Note,
+    note_jump: &NoteJump,
+    colors: &ColorScheme,
+) -> (transform::Transform, drawable::Drawable) {
+    let note_texture = match (note.note_type, note.direction) {
+        // Dots and chain links have no arrow
+        (NoteType::Red, Direction::NoDirection) => "note_middle_red",
+        (NoteType::Blue, Direction::NoDirection) => "note_middle_blue",
+        (NoteType::Red, _) => "note_red",
+        (NoteType::Blue, _) => "note_blue",
+        (NoteType::Mine, _) => "mine",
+    }
+    .to_string();
 
-        Direction::NoDirection => UnitQuaternion::from_euler_angles(0.0, 0.0, 0.0),
+    let (note_model, shader) = match note.note_type {
+        NoteType::Mine => ("mine", "simple"),
+        // The note shader paints the texture in the colour of the tint
+        _ => ("block", "note"),
     };
 
-    let drawable = drawable::Drawable::new(note_model, note_texture, "simple".to_string());
+    let mut drawable =
+        drawable::Drawable::new(note_model.to_string(), note_texture, shader.to_string());
+    drawable.tint = colors.note_tint(note.note_type);
+    let position = note_position(note);
     let transform = transform::Transform::new(
-        nalgebra::Translation3::new(
-            -(note.line_index as f32 * 0.7) + 1.0,
-            note.line_layer as f32 * 0.6 + 1.0,
-            note.time / 60.0 + 5.0,
+        lane_position(
+            note.rotation,
+            position.x,
+            position.y,
+            note_jump.distance(note.time) + HIT_PLANE_Z,
         ),
-        note_direction,
-        nalgebra::Vector3::new(0.3, 0.3, 0.3),
+        lane_rotation(note.rotation) * note_rotation(note),
+        // Links are flat slices along the chain
+        match note.link {
+            Some(_) => nalgebra::Vector3::new(0.3, 0.1, 0.3),
+            None => nalgebra::Vector3::new(0.3, 0.3, 0.3),
+        },
     );
-
-    world
-        .create_entity()
-        .with(transform)
-        .with(note)
-        .with(drawable)
-        .build();
+    (transform, drawable)
 }
 
-pub fn place_obstacle(world: &mut specs::World, obstacle: obstacle::Obstacle) {
-    let scale = match obstacle.obstacle_type {
-        obstacle::ObstacleType::Wall => {
-            nalgebra::Vector3::new(obstacle.width as f32 * 0.3, 2.0, obstacle.duration / 60.0)
-        }
-        obstacle::ObstacleType::Ceiling => {
-            nalgebra::Vector3::new(1.2, obstacle.width as f32 * 0.3, obstacle.duration / 60.0)
-        }
+/// Shape of an arc in the space of its lane, starting at z = 0 and moving along with notes
+pub fn arc_curve(arc: &arc::Arc, note_jump: &NoteJump) -> crate::arcs::Bezier {
+    use crate::arcs::{grid_direction, playfield_vector, ARC_CONTROL_DISTANCE};
+    use nalgebra::Vector3;
+    let length = note_jump.distance(arc.tail_time - arc.time);
+    let head = grid_position(arc.line_index as f32, arc.line_layer as f32);
+    let tail = grid_position(arc.tail_line_index as f32, arc.tail_line_layer as f32);
+    let control = |direction, multiplier: f32| {
+        playfield_vector(grid_direction(direction))
+            .try_normalize(1.0e-6)
+            .unwrap_or_else(Vector2::zeros)
+            * multiplier
+            * ARC_CONTROL_DISTANCE
     };
-    let position = match obstacle.obstacle_type {
-        obstacle::ObstacleType::Wall => {
-            nalgebra::Translation3::new(
-                -(obstacle.line_index as f32 * 1.5) + 2.0,
-                2.0,
-                obstacle.time / 60.0 + 5.0 + obstacle.duration / 120.0,
-            )
-        }
-        obstacle::ObstacleType::Ceiling => {
-            nalgebra::Translation3::new(
-                -(obstacle.line_index as f32 * 1.5) + 0.8,
-                3.2 - (obstacle.width / 2) as f32,
-                obstacle.time / 60.0 + 5.0 + obstacle.duration / 120.0,
-            )
-        }
+    let head_control = head + control(arc.direction, arc.multiplier);
+    // The arc reaches the tail along the cut direction of the tail
+    let tail_control = tail - control(arc.tail_direction, arc.tail_multiplier);
+    // Evenly spaced z keeps the curve moving along z as fast as time passes
+    crate::arcs::Bezier {
+        points: [
+            Vector3::new(head.x, head.y, 0.0),
+            Vector3::new(head_control.x, head_control.y, length / 3.0),
+            Vector3::new(tail_control.x, tail_control.y, length * 2.0 / 3.0),
+            Vector3::new(tail.x, tail.y, length),
+        ],
+    }
+}
+
+pub fn arc_components(
+    arc: &arc::Arc,
+    note_jump: &NoteJump,
+    colors: &ColorScheme,
+) -> (transform::Transform, drawable::Drawable, arc::Curve) {
+    let transform = transform::Transform::new(
+        lane_position(arc.rotation, 0.0, 0.0, note_jump.distance(arc.time) + HIT_PLANE_Z),
+        lane_rotation(arc.rotation),
+        nalgebra::Vector3::new(1.0, 1.0, 1.0),
+    );
+    let mut drawable =
+        drawable::Drawable::new("arc".to_string(), "dev".to_string(), "light".to_string());
+    let [r, g, b, _] = colors.note_tint(arc.note_type);
+    drawable.tint = [r, g, b, 0.8];
+    let curve = arc::Curve {
+        bezier: arc_curve(arc, note_jump),
     };
+    (transform, drawable, curve)
+}
+
+/// World position of a point in a lane turned `rotation` degrees around the player
+pub fn lane_position(rotation: f32, x: f32, y: f32, z: f32) -> nalgebra::Translation3<f32> {
+    nalgebra::Translation3::from(lane_rotation(rotation) * nalgebra::Vector3::new(x, y, z))
+}
+
+/// Centre and half size of the area an obstacle covers across the playfield.
+/// Obstacles fill whole grid cells, lanes are 0.7 wide and layers are 0.6 high
+pub fn obstacle_footprint(obstacle: &obstacle::Obstacle) -> (Vector2<f32>, Vector2<f32>) {
+    // The centre of the cells in the middle of the wall
+    let center = grid_position(
+        obstacle.line_index as f32 + (obstacle.width - 1) as f32 / 2.0,
+        obstacle.line_layer as f32 + (obstacle.height - 1) as f32 / 2.0,
+    );
+    let half_size = Vector2::new(
+        obstacle.width as f32 * 0.35,
+        obstacle.height as f32 * 0.3,
+    );
+    (center, half_size)
+}
+
+pub fn obstacle_components(
+    obstacle: &obstacle::Obstacle,
+    note_jump: &NoteJump,
+    colors: &ColorScheme,
+) -> (transform::Transform, drawable::Drawable) {
+    let (center, half_size) = obstacle_footprint(obstacle);
+    let scale = nalgebra::Vector3::new(
+        half_size.x,
+        half_size.y,
+        note_jump.distance(obstacle.duration) / 2.0,
+    );
+    let position = lane_position(
+        obstacle.rotation,
+        center.x,
+        center.y,
+        note_jump.distance(obstacle.time) + HIT_PLANE_Z + note_jump.distance(obstacle.duration) / 2.0,
+    );
     let transform = transform::Transform::new(
         position,
-        UnitQuaternion::from_euler_angles(0.0, 0.0, 0.0),
+        lane_rotation(obstacle.rotation),
         scale,
     );
-    let drawable = drawable::Drawable::new(
+    let mut drawable = drawable::Drawable::new(
         "cube".to_string(),
         "obstacle".to_string(),
         "wall".to_string(),
     );
-    world
-        .create_entity()
-        .with(transform)
-        .with(obstacle)
-        .with(drawable)
-        .build();
+    drawable.tint = obstacle::wall_tint(colors);
+    (transform, drawable)
 }
 
 pub fn init_song(parsed_song: crate::parser::ParsedSong, world: &mut specs::World) {
+    let modifiers = *world.read_resource::<Modifiers>();
+    let (note_jump_speed, autoplay, colors) = {
+        let settings = world.read_resource::<PlayerSettings>();
+        let colors = if settings.ignore_map_colors {
+            settings.color_scheme
+        } else {
+            settings.color_scheme.with_overrides(&parsed_song.colors)
+        };
+        (
+            settings.note_jump_speed.unwrap_or(parsed_song.note_jump_speed),
+            settings.autoplay,
+            colors,
+        )
+    };
+    let note_jump = NoteJump::new(
+        parsed_song.bpm,
+        note_jump_speed,
+        parsed_song.note_jump_offset,
+    );
+    if autoplay {
+        *world.write_resource::<AutoplayPlan>() =
+            AutoplayPlan::new(&parsed_song.notes, &parsed_song.obstacles, &note_jump);
+    }
     {
         let parsed_song_info = CurrentSongInfo {
-            bpm: parsed_song.bpm,
-            bpb: parsed_song.bpb,
-            time: parsed_song.time,
+            tempo_map: parsed_song.tempo_map,
+            note_jump,
+            song_file: String::new(),
+            map_hash: String::new(),
+            folder: String::new(),
+            characteristic: String::new(),
+            difficulty: String::new(),
+            lane_rotations: parsed_song.lane_rotations,
         };
         let mut song_info = world.write_resource::<CurrentSongInfo>();
         *song_info = parsed_song_info;
     }
 
-    for note in parsed_song.notes {
-        place_note(world, note);
-    }
-    for obstacle in parsed_song.obstacles {
-        place_obstacle(world, obstacle);
-    }
+    *world.write_resource::<score::SongScore>() = score::SongScore {
+        modifier_multiplier: modifiers.score_multiplier(),
+        ..Default::default()
+    };
+    *world.write_resource::<stats::PlayerStats>() = stats::PlayerStats::new(&modifiers);
+    world.write_resource::<replay::ReplayRecording>().frames.clear();
+    world.write_resource::<clock::SongClock>().speed = modifiers.song_speed.ratio();
+    *world.write_resource::<lights::LightEvents>() =
+        lights::LightEvents::new(parsed_song.light_events);
+    set_color_scheme(colors, world);
+    *world.write_resource::<lane::ActiveLane>() = Default::default();
+    *world.write_resource::<obstacle::HeadInWall>() = Default::default();
+    let mut spawn_queue = world.write_resource::<spawn::SpawnQueue>();
+    *spawn_queue =
+        spawn::SpawnQueue::new(parsed_song.notes, parsed_song.obstacles, parsed_song.arcs);
+}
+
+/// Makes `colors` the active colour scheme, this also resets the environment lights
+pub fn set_color_scheme(colors: ColorScheme, world: &mut specs::World) {
+    *world.write_resource::<ColorScheme>() = colors;
+    *world.write_resource::<lights::EnvironmentLights>() =
+        lights::EnvironmentLights::new(colors.lights);
 }
 
 pub fn load_song(
     name: String,
+    characteristic: String,
     difficulty: String,
     world: &mut specs::World,
-) -> Result<(), std::io::Error> {
-    let parsed_song = crate::parser::open_file(&std::path::Path::new(&format!(
-        "./assets/songs/{}/{}.json",
-        name, difficulty
-    )))?;
+) -> Result<crate::parser::ParseReport, crate::library::LibraryError> {
+    let song_dirs = world.read_resource::<PlayerSettings>().song_dirs.clone();
+    let library = crate::library::Library::load(&song_dirs);
+    let song_dir = library.resolve(&song_dirs, &name, &characteristic, &difficulty)?;
+    let (info, mut parsed_song) =
+        crate::parser::open_song(&song_dir, &characteristic, &difficulty)?;
+    println!(
+        "Loading {} {} by {}, mapped by {}",
+        info.song_name, info.song_sub_name, info.song_author_name, info.level_author_name
+    );
+    let report = std::mem::take(&mut parsed_song.report);
+    // `open_song` found the beatmap, so the names are there
+    let beatmap = info.find_beatmap(&characteristic, &difficulty).unwrap();
+    let characteristic = info.characteristic_of(beatmap).unwrap().to_string();
+    let difficulty = beatmap.difficulty.clone();
     let song_file = parsed_song.song_file.clone();
-    // Yes yes, that's not real song name, that's just an folder name. I know it. I'm just too lazy to do something better FIXME
+    world.read_resource::<Modifiers>().apply(&mut parsed_song);
     init_song(parsed_song, world);
+    // `name` only finds the folder, scores and replays know the map by its hash
+    let map_hash = crate::parser::map_hash(&info).unwrap_or_else(|e| {
+        println!("Couldn't hash the map: {}", e);
+        String::new()
+    });
+    let mut song_info = world.write_resource::<CurrentSongInfo>();
+    song_info.song_file = song_dir.join(song_file).to_string_lossy().into_owned();
+    song_info.map_hash = map_hash;
+    song_info.folder = song_dir
+        .file_name()
+        .map(|folder| folder.to_string_lossy().into_owned())
+        .unwrap_or(name);
+    song_info.characteristic = characteristic;
+    song_info.difficulty = difficulty;
+    Ok(report)
+}
 
+pub fn play_song_audio(world: &mut specs::World) {
+    let song_file = world.read_resource::<CurrentSongInfo>().song_file.clone();
+    let speed = world.read_resource::<Modifiers>().song_speed.ratio();
     let mut sound_events = world.write_resource::<sound::SoundEvents>();
-    let audio_start_event = sound::SoundEvent::AddSound(
-        format!("./assets/songs/{}/{}", name, song_file),
-        Some("SongPlayback".to_string()),
+    let audio_start_event = sound::SoundEvent::AddSoundWithSpeed(
+        song_file,
+        Some(clock::SONG_SOUND.to_string()),
+        speed,
     );
     sound_events.queue.push(audio_start_event);
-    Ok(())
+}
+
+/// Removes everything the current song spawned and stops its audio
+pub fn unload_song(world: &mut specs::World) {
+    use specs::Join;
+    {
+        let ents = world.entities();
+        let notes = world.read_storage::<note::Note>();
+        let obstacles = world.read_storage::<obstacle::Obstacle>();
+        let arcs = world.read_storage::<arc::Arc>();
+        let mut ents_to_remove = world.write_resource::<RemoveEntities>();
+        for (ent, _) in (&ents, &notes).join() {
+            ents_to_remove.0.push(ent);
+        }
+        for (ent, _) in (&ents, &obstacles).join() {
+            ents_to_remove.0.push(ent);
+        }
+        for (ent, _) in (&ents, &arcs).join() {
+            ents_to_remove.0.push(ent);
+        }
+    }
+    *world.write_resource::<spawn::SpawnQueue>() = Default::default();
+    *world.write_resource::<AutoplayPlan>() = Default::default();
+    *world.write_resource::<lights::LightEvents>() = Default::default();
+    let colors = world.read_resource::<PlayerSettings>().color_scheme;
+    set_color_scheme(colors, world);
+    *world.write_resource::<lane::ActiveLane>() = Default::default();
+    *world.write_resource::<obstacle::HeadInWall>() = Default::default();
+    *world.write_resource::<clock::SongClock>() = Default::default();
+    world
+        .write_resource::<sound::SoundEvents>()
+        .queue
+        .push(sound::SoundEvent::StopSound(clock::SONG_SOUND.to_string()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wall(line_index: i32, line_layer: i32, width: i32, height: i32) -> obstacle::Obstacle {
+        obstacle::Obstacle {
+            line_index,
+            line_layer,
+            width,
+            height,
+            time: 0.0,
+            duration: 1000.0,
+            rotation: 0.0,
+        }
+    }
+
+    #[test]
+    fn walls_cover_the_cells_of_notes() {
+        let (center, half_size) = obstacle_footprint(&wall(1, 2, 1, 1));
+        assert_eq!(center, grid_position(1.0, 2.0));
+        assert_eq!(half_size, Vector2::new(0.35, 0.3));
+
+        // A full height wall over the two left lanes reaches from below the bottom notes
+        // to above the top ones
+        let (center, half_size) = obstacle_footprint(&wall(0, 0, 2, 5));
+        let bottom_left = grid_position(0.0, 0.0);
+        let top_right = grid_position(1.0, 2.0);
+        assert!((center.x - (bottom_left.x + top_right.x) / 2.0).abs() < 1e-5);
+        assert!(center.y - half_size.y < bottom_left.y - 0.3 + 1e-5);
+        assert!(center.y + half_size.y > top_right.y + 0.3);
+    }
 }
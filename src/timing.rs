@@ -0,0 +1,204 @@
+/// Tempo used in place of a missing or broken one
+const FALLBACK_BPM: f32 = 120.0;
+
+/// Whether `bpm` can be used to convert beats to time
+pub fn is_valid_bpm(bpm: f32) -> bool {
+    bpm.is_finite() && bpm > 0.0
+}
+
+/// A point where the tempo of the map changes. `seconds` is the absolute song time of `beat`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TempoChange {
+    pub beat: f32,
+    pub bpm: f32,
+    pub seconds: f32,
+}
+
+/// Piecewise tempo of a map, converts beats to song time and back
+#[derive(Clone, Debug)]
+pub struct TempoMap {
+    changes: Vec<TempoChange>,
+}
+
+impl Default for TempoMap {
+    fn default() -> Self {
+        Self::new(FALLBACK_BPM, &[])
+    }
+}
+
+impl TempoMap {
+    /// `changes` are `(beat, bpm)` pairs in any order, `bpm` is the tempo before the first change.
+    /// Broken changes are dropped and a broken `bpm` falls back to 120, the parser rejects those
+    pub fn new(bpm: f32, changes: &[(f32, f32)]) -> Self {
+        let bpm = if is_valid_bpm(bpm) { bpm } else { FALLBACK_BPM };
+        let mut sorted: Vec<(f32, f32)> = changes
+            .iter()
+            .cloned()
+            .filter(|(beat, bpm)| *beat >= 0.0 && beat.is_finite() && is_valid_bpm(*bpm))
+            .collect();
+        sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut tempo_changes = vec![TempoChange {
+            beat: 0.0,
+            bpm,
+            seconds: 0.0,
+        }];
+        for (beat, bpm) in sorted {
+            let last = *tempo_changes.last().unwrap();
+            let change = TempoChange {
+                beat,
+                bpm,
+                seconds: last.seconds + (beat - last.beat) * 60.0 / last.bpm,
+            };
+            // A change at the same beat replaces the previous one
+            if beat == last.beat {
+                *tempo_changes.last_mut().unwrap() = change;
+            } else {
+                tempo_changes.push(change);
+            }
+        }
+        Self {
+            changes: tempo_changes,
+        }
+    }
+    fn change_at_beat(&self, beat: f32) -> &TempoChange {
+        let index = self
+            .changes
+            .iter()
+            .rposition(|change| change.beat <= beat)
+            .unwrap_or(0);
+        &self.changes[index]
+    }
+    fn change_at_seconds(&self, seconds: f32) -> &TempoChange {
+        let index = self
+            .changes
+            .iter()
+            .rposition(|change| change.seconds <= seconds)
+            .unwrap_or(0);
+        &self.changes[index]
+    }
+    pub fn beat_to_seconds(&self, beat: f32) -> f32 {
+        let change = self.change_at_beat(beat);
+        change.seconds + (beat - change.beat) * 60.0 / change.bpm
+    }
+    pub fn seconds_to_beat(&self, seconds: f32) -> f32 {
+        let change = self.change_at_seconds(seconds);
+        change.beat + (seconds - change.seconds) * change.bpm / 60.0
+    }
+    /// Song time in milliseconds, the unit game objects are stored in
+    pub fn beat_to_ms(&self, beat: f32) -> f32 {
+        self.beat_to_seconds(beat) * 1000.0
+    }
+    /// Length in milliseconds of `beats` beats starting at `beat`
+    pub fn duration_ms(&self, beat: f32, beats: f32) -> f32 {
+        self.beat_to_ms(beat + beats) - self.beat_to_ms(beat)
+    }
+}
+
+/// How fast objects approach the player and how long they are visible, derived like in Beat Saber
+#[derive(Clone, Copy, Debug)]
+pub struct NoteJump {
+    // Metres per second
+    pub speed: f32,
+    // Seconds from the moment an object appears until it reaches the player
+    pub half_jump_duration: f32,
+    // Distance covered during the whole jump, half of it before the player and half after
+    pub jump_distance: f32,
+}
+
+impl Default for NoteJump {
+    fn default() -> Self {
+        Self::new(FALLBACK_BPM, 10.0, 0.0)
+    }
+}
+
+impl NoteJump {
+    /// A broken `bpm` falls back to 120 like in `TempoMap::new`
+    pub fn new(bpm: f32, speed: f32, start_beat_offset: f32) -> Self {
+        let bpm = if is_valid_bpm(bpm) { bpm } else { FALLBACK_BPM };
+        let seconds_per_beat = 60.0 / bpm;
+        let mut half_jump_beats = 4.0;
+        while speed * seconds_per_beat * half_jump_beats > 17.999 {
+            half_jump_beats /= 2.0;
+        }
+        half_jump_beats += start_beat_offset;
+        if half_jump_beats < 0.25 {
+            half_jump_beats = 0.25;
+        }
+        let half_jump_duration = half_jump_beats * seconds_per_beat;
+        Self {
+            speed,
+            half_jump_duration,
+            jump_distance: speed * half_jump_duration * 2.0,
+        }
+    }
+    /// Metres covered in `ms` milliseconds of song time
+    pub fn distance(&self, ms: f32) -> f32 {
+        ms * self.speed / 1000.0
+    }
+    /// How far in front of the player objects appear
+    pub fn spawn_distance(&self) -> f32 {
+        self.jump_distance / 2.0
+    }
+}
+
+/// Note jump speed Beat Saber uses when a map doesn't define one
+pub fn default_note_jump_speed(difficulty: &str) -> f32 {
+    match difficulty.to_lowercase().as_str() {
+        "expert" => 12.0,
+        "expertplus" => 16.0,
+        _ => 10.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1.0e-3, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn beats_round_trip_through_tempo_changes() {
+        // Changes come in any order: 8 beats at 120, 8 at 180, 16 at 60 and then 240
+        let tempo_map = TempoMap::new(120.0, &[(16.0, 60.0), (8.0, 180.0), (32.0, 240.0)]);
+        assert_close(tempo_map.beat_to_seconds(8.0), 4.0);
+        assert_close(tempo_map.beat_to_seconds(16.0), 4.0 + 8.0 / 3.0);
+        assert_close(tempo_map.beat_to_seconds(32.0), 4.0 + 8.0 / 3.0 + 16.0);
+        assert_close(tempo_map.beat_to_seconds(40.0), 4.0 + 8.0 / 3.0 + 16.0 + 2.0);
+        for i in 0..200 {
+            let beat = i as f32 * 0.25;
+            assert_close(tempo_map.seconds_to_beat(tempo_map.beat_to_seconds(beat)), beat);
+        }
+    }
+
+    #[test]
+    fn durations_span_tempo_changes() {
+        let tempo_map = TempoMap::new(120.0, &[(4.0, 60.0)]);
+        // Two beats at 120 and two at 60
+        assert_close(tempo_map.duration_ms(2.0, 4.0), 3000.0);
+    }
+
+    #[test]
+    fn broken_and_repeated_changes() {
+        // Negative beats and tempos are dropped, the last change at a beat wins
+        let tempo_map = TempoMap::new(
+            120.0,
+            &[(-4.0, 60.0), (4.0, 0.0), (4.0, 240.0), (4.0, 60.0)],
+        );
+        assert_close(tempo_map.beat_to_seconds(4.0), 2.0);
+        assert_close(tempo_map.beat_to_seconds(5.0), 3.0);
+    }
+
+    #[test]
+    fn broken_base_tempo_falls_back() {
+        for bpm in [0.0, -60.0, f32::NAN, f32::INFINITY].iter() {
+            let tempo_map = TempoMap::new(*bpm, &[(4.0, f32::NAN), (f32::NAN, 60.0)]);
+            assert_close(tempo_map.beat_to_seconds(4.0), 2.0);
+            let note_jump = NoteJump::new(*bpm, 10.0, 0.0);
+            assert!(note_jump.half_jump_duration.is_finite());
+            assert_close(note_jump.jump_distance, NoteJump::default().jump_distance);
+        }
+    }
+}